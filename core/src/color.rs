@@ -1,6 +1,48 @@
-use std::ops::{Add, BitAnd, Mul, Shr, Sub};
+use {
+	crate::math::Vector4,
+	std::{
+		ops::{Add, BitAnd, Mul, Shr, Sub},
+		sync::LazyLock,
+	},
+};
+
+/// Entries of the linear to sRGB table, dense enough to round trip every
+/// 8-bit value.
+const ENCODE_STEPS: usize = 4096;
+
+/// Linear value of every 8-bit sRGB channel.
+static DECODE_TABLE: LazyLock<[f32; 256]> =
+	LazyLock::new(|| std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0)));
+
+/// 8-bit sRGB channel of evenly spaced linear values.
+static ENCODE_TABLE: LazyLock<[u8; ENCODE_STEPS]> = LazyLock::new(|| {
+	std::array::from_fn(|i| {
+		let linear = i as f32 / (ENCODE_STEPS - 1) as f32;
+		(linear_to_srgb(linear) * 255.0 + 0.5) as u8
+	})
+});
+
+/// Decodes an sRGB encoded channel in `[0, 1]` to linear.
+#[inline]
+pub fn srgb_to_linear(c: f32) -> f32 {
+	if c <= 0.04045 {
+		c / 12.92
+	} else {
+		((c + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+/// Encodes a linear channel in `[0, 1]` to sRGB.
+#[inline]
+pub fn linear_to_srgb(c: f32) -> f32 {
+	if c <= 0.003_130_8 {
+		c * 12.92
+	} else {
+		1.055 * c.powf(1.0 / 2.4) - 0.055
+	}
+}
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct Color(f32, f32, f32, f32);
 
 impl Default for Color {
@@ -41,6 +83,8 @@ impl Color {
 		Self::new_rgb(v, v, v)
 	}
 
+	/// Parses `#rrggbb` or `#rrggbbaa`, the channels are sRGB encoded and
+	/// decoded to linear.
 	pub fn from_hex(hex: &str) -> Option<Self> {
 		let hex = hex.trim_start_matches('#');
 		let len = hex.len();
@@ -50,14 +94,14 @@ impl Color {
 				let r = u8::from_str_radix(&hex[0..2], 16).ok()? as f32 / 255.0;
 				let g = u8::from_str_radix(&hex[2..4], 16).ok()? as f32 / 255.0;
 				let b = u8::from_str_radix(&hex[4..6], 16).ok()? as f32 / 255.0;
-				Some(Color::new_rgb(r, g, b))
+				Some(Color::new_rgb(r, g, b).to_linear())
 			}
 			8 => {
 				let r = u8::from_str_radix(&hex[0..2], 16).ok()? as f32 / 255.0;
 				let g = u8::from_str_radix(&hex[2..4], 16).ok()? as f32 / 255.0;
 				let b = u8::from_str_radix(&hex[4..6], 16).ok()? as f32 / 255.0;
 				let a = u8::from_str_radix(&hex[6..8], 16).ok()? as f32 / 255.0;
-				Some(Color::new(r, g, b, a))
+				Some(Color::new(r, g, b, a).to_linear())
 			}
 			_ => None,
 		}
@@ -79,6 +123,71 @@ impl Color {
 		]
 	}
 
+	#[inline(always)]
+	pub fn from_rgba8(rgba: [u8; 4]) -> Color {
+		Color(
+			rgba[0] as f32 / 255.0,
+			rgba[1] as f32 / 255.0,
+			rgba[2] as f32 / 255.0,
+			rgba[3] as f32 / 255.0,
+		)
+	}
+
+	/// Color of sRGB encoded 8-bit channels, the alpha is kept linear.
+	#[inline(always)]
+	pub fn from_srgba8(rgba: [u8; 4]) -> Color {
+		let table = &*DECODE_TABLE;
+
+		Color(
+			table[rgba[0] as usize],
+			table[rgba[1] as usize],
+			table[rgba[2] as usize],
+			rgba[3] as f32 / 255.0,
+		)
+	}
+
+	/// 8-bit sRGB encoded channels, values outside `[0, 1]` are clamped.
+	#[inline(always)]
+	pub fn to_srgba8(&self) -> [u8; 4] {
+		let table = &*ENCODE_TABLE;
+		let scale = (ENCODE_STEPS - 1) as f32;
+
+		let encode = |c: f32| table[(c.clamp(0.0, 1.0) * scale + 0.5) as usize];
+
+		[
+			encode(self.0),
+			encode(self.1),
+			encode(self.2),
+			(self.3.clamp(0.0, 1.0) * 255.0 + 0.5) as u8,
+		]
+	}
+
+	/// Decodes the sRGB encoded channels to linear, the alpha is kept.
+	pub fn to_linear(&self) -> Color {
+		Color(
+			srgb_to_linear(self.0),
+			srgb_to_linear(self.1),
+			srgb_to_linear(self.2),
+			self.3,
+		)
+	}
+
+	/// Encodes the linear channels to sRGB, the alpha is kept.
+	pub fn to_srgb(&self) -> Color {
+		Color(
+			linear_to_srgb(self.0),
+			linear_to_srgb(self.1),
+			linear_to_srgb(self.2),
+			self.3,
+		)
+	}
+
+	/// Brightest of the red, green and blue channels.
+	#[inline(always)]
+	pub fn max_channel(&self) -> f32 {
+		self.0.max(self.1).max(self.2)
+	}
+
 	#[inline(always)]
 	pub fn add_raw(self, other: Color) -> Color {
 		Color(
@@ -90,6 +199,20 @@ impl Color {
 	}
 }
 
+impl From<Color> for Vector4 {
+	#[inline(always)]
+	fn from(c: Color) -> Self {
+		Vector4::new(c.0, c.1, c.2, c.3)
+	}
+}
+
+impl From<Vector4> for Color {
+	#[inline(always)]
+	fn from(v: Vector4) -> Self {
+		Color::new(v.x, v.y, v.z, v.w)
+	}
+}
+
 impl Add for Color {
 	type Output = Color;
 
@@ -322,3 +445,16 @@ impl Mul for Color32 {
 		Color32(((rb & 0x00FF00FF) | ((ag & 0x00FF00FF) << 8)) as u32)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::Color;
+
+	#[test]
+	fn srgb_round_trip() {
+		for v in 0..=255 {
+			let color = Color::from_srgba8([v, v, v, v]);
+			assert_eq!(color.to_srgba8(), [v, v, v, v]);
+		}
+	}
+}
@@ -26,6 +26,9 @@ pub enum PError {
 
 	#[error("Error occured while texture loading: {0}")]
 	TextureLoading(#[from] ImageError),
+
+	#[error("Asset not found in the registry: {0}")]
+	MissingAsset(&'static str),
 }
 
 #[derive(Debug, Error)]
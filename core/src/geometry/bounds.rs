@@ -0,0 +1,157 @@
+use crate::math::{Matrix4, Vector3, Vector4};
+
+/// Sphere enclosing a set of points.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct BoundingSphere {
+	pub center: Vector3,
+	pub radius: f32,
+}
+
+impl BoundingSphere {
+	pub fn new(center: Vector3, radius: f32) -> Self {
+		Self { center, radius }
+	}
+
+	/// Sphere centered on the axis aligned bounds of the points, not the
+	/// tightest fit but cheap and stable.
+	pub fn from_points(points: &[Vector3]) -> Self {
+		let Some(first) = points.first() else {
+			return Self::default();
+		};
+
+		let (min, max) = points.iter().fold((*first, *first), |(min, max), p| {
+			(
+				Vector3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+				Vector3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+			)
+		});
+
+		let center = (min + max) * 0.5;
+		let radius = points
+			.iter()
+			.map(|p| (*p - center).magnitude())
+			.fold(0.0, f32::max);
+
+		Self { center, radius }
+	}
+
+	/// Transforms the sphere by an affine matrix, the radius is scaled by
+	/// the largest axis scale so the result stays conservative.
+	pub fn transform(&self, m: &Matrix4) -> Self {
+		let center = (*m * Vector4::from((self.center, 1.0))).xyz();
+
+		let d = m.data;
+		let scale = (0..3)
+			.map(|c| Vector3::new(d[0][c], d[1][c], d[2][c]).magnitude())
+			.fold(0.0, f32::max);
+
+		Self {
+			center,
+			radius: self.radius * scale,
+		}
+	}
+}
+
+/// Clip space planes extracted from a view-projection matrix, each plane
+/// is stored as (normal, distance) with the normal pointing inside.
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+	pub planes: [Vector4; 6],
+}
+
+impl Frustum {
+	/// Gribb-Hartmann plane extraction, expects the -w <= z <= w clip
+	/// volume produced by `Matrix4::perspective_matrix`.
+	pub fn from_matrix(m: &Matrix4) -> Self {
+		let row = |i: usize| {
+			Vector4::new(m.data[i][0], m.data[i][1], m.data[i][2], m.data[i][3])
+		};
+
+		let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+
+		let planes = [
+			r3 + r0, // Left
+			r3 - r0, // Right
+			r3 + r1, // Bottom
+			r3 - r1, // Top
+			r3 + r2, // Near
+			r3 - r2, // Far
+		]
+		.map(|p| p * (1.0 / p.xyz().magnitude()));
+
+		Self { planes }
+	}
+
+	pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+		self
+			.planes
+			.iter()
+			.all(|p| p.xyz().dot(&sphere.center) + p.w >= -sphere.radius)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::{BoundingSphere, Frustum},
+		crate::math::{Matrix4, Vector3},
+	};
+
+	#[test]
+	fn sphere_encloses_the_points() {
+		let points = [
+			Vector3::new(-1.0, 0.0, 2.0),
+			Vector3::new(3.0, 2.0, 2.0),
+			Vector3::new(1.0, 1.0, 4.0),
+		];
+		let sphere = BoundingSphere::from_points(&points);
+
+		assert!((sphere.center - Vector3::new(1.0, 1.0, 3.0)).magnitude() < 1e-5);
+		assert!(
+			points
+				.iter()
+				.all(|p| (*p - sphere.center).magnitude() <= sphere.radius + 1e-5)
+		);
+	}
+
+	#[test]
+	fn transformed_sphere_follows_the_largest_scale() {
+		let sphere = BoundingSphere::new(Vector3::new(1.0, 0.0, 0.0), 2.0);
+		let m = Matrix4::translation_matrix(0.0, 5.0, 0.0)
+			* Matrix4::scale_matrix(1.0, 3.0, 2.0);
+
+		let moved = sphere.transform(&m);
+
+		assert!((moved.center - Vector3::new(1.0, 5.0, 0.0)).magnitude() < 1e-5);
+		assert!((moved.radius - 6.0).abs() < 1e-5);
+	}
+
+	#[test]
+	fn frustum_keeps_the_spheres_in_view() {
+		// Camera at the origin looking down -z.
+		let fov = std::f32::consts::FRAC_PI_2;
+		let frustum =
+			Frustum::from_matrix(&Matrix4::perspective_matrix(fov, 1.0, 0.1, 100.0));
+		let visible = |x, y, z, radius| {
+			frustum
+				.intersects_sphere(&BoundingSphere::new(Vector3::new(x, y, z), radius))
+		};
+
+		assert!(visible(0.0, 0.0, -5.0, 1.0));
+		// Straddling the left plane, |x| = |z| at 90 degrees.
+		assert!(visible(-5.5, 0.0, -5.0, 1.0));
+
+		assert!(!visible(0.0, 0.0, 5.0, 1.0));
+		assert!(!visible(-8.0, 0.0, -5.0, 1.0));
+		assert!(!visible(0.0, 8.0, -5.0, 1.0));
+		assert!(!visible(0.0, 0.0, -150.0, 1.0));
+
+		// The far plane is at infinity with reverse-Z.
+		let frustum = Frustum::from_matrix(&Matrix4::reverse_z_perspective_matrix(
+			fov, 1.0, 0.1,
+		));
+		let sphere = BoundingSphere::new(Vector3::new(0.0, 0.0, -150.0), 1.0);
+
+		assert!(frustum.intersects_sphere(&sphere));
+	}
+}
@@ -1,9 +1,13 @@
-use crate::{
-	geometry::{
-		BiTangent, Idx, NIdx, Normal, Normals, TIdx, Tangent, Triangles, UV, VIdx,
-		Vertex, Vertices,
+use {
+	crate::{
+		color::Color,
+		geometry::{
+			BiTangent, BoundingSphere, Idx, NIdx, Normal, Normals, TIdx, Tangent,
+			Triangles, UV, VIdx, Vertex, VertexAttributes, Vertices,
+		},
+		math::Vector3,
 	},
-	math::Vector3,
+	std::collections::HashMap,
 };
 
 #[derive(Debug, Default)]
@@ -26,7 +30,12 @@ impl Indices {
 		if index >= self.len() {
 			return (0, 0, 0);
 		}
-		(self.v[index], self.n[index], self.uv[index])
+
+		// Normal and uv indices may be missing, e.g. on meshes without uv.
+		let n = self.n.get(index).copied().unwrap_or_default();
+		let uv = self.uv.get(index).copied().unwrap_or_default();
+
+		(self.v[index], n, uv)
 	}
 
 	pub fn push_v_index(&mut self, idx: Idx) {
@@ -49,7 +58,16 @@ pub struct Mesh {
 	pub uv: Vec<UV>,
 	pub tangents: Vec<Tangent>,
 	pub bi_tangents: Vec<BiTangent>,
+	/// Colors of the vertices indexed like `vertices`, empty when the mesh
+	/// is not colored.
+	pub colors: Vec<Color>,
 	pub indices: Indices,
+	pub bounds: BoundingSphere,
+	/// Resolved attributes of every unique position, normal and uv index
+	/// combination, so vertex processing runs once per shared vertex.
+	vertex_buffer: Vec<VertexAttributes>,
+	/// Three `vertex_buffer` entries per triangle.
+	index_buffer: Vec<u32>,
 }
 
 impl Mesh {
@@ -62,25 +80,83 @@ impl Mesh {
 		let (tangents, bi_tangents) =
 			Self::bake_mesh(&vertices, &mut indices, &mut uv, &mut vnormals);
 
-		Self {
+		let mut mesh = Self {
+			bounds: BoundingSphere::from_points(&vertices),
 			vertices,
 			indices,
 			uv,
 			tangents,
 			bi_tangents,
+			colors: Vec::new(),
 			normals: vnormals,
+			vertex_buffer: Vec::new(),
+			index_buffer: Vec::new(),
+		};
+
+		mesh.build_vertex_buffer();
+		mesh
+	}
+
+	/// Deduplicates the index tuples of the faces into `vertex_buffer` and
+	/// `index_buffer`, incomplete trailing triangles are dropped.
+	fn build_vertex_buffer(&mut self) {
+		let count = self.indices.len() / 3 * 3;
+
+		let mut unique = HashMap::with_capacity(count);
+		let mut vertex_buffer = Vec::new();
+		let mut index_buffer = Vec::with_capacity(count);
+
+		for (index, attributes) in self.iter_triangles().flatten().enumerate() {
+			let key = self.indices.index(index);
+
+			let next = unique.len() as u32;
+			let slot = *unique.entry(key).or_insert(next);
+
+			if slot == next {
+				vertex_buffer.push(attributes);
+			}
+
+			index_buffer.push(slot);
 		}
+
+		self.vertex_buffer = vertex_buffer;
+		self.index_buffer = index_buffer;
+
+		debug_assert_eq!(self.index_buffer.len(), count);
+	}
+
+	/// Attaches a color to every vertex position, the missing ones are
+	/// white.
+	pub fn with_colors(mut self, mut colors: Vec<Color>) -> Self {
+		colors.resize(self.vertices.len(), Color::WHITE);
+
+		self.colors = colors;
+		self.build_vertex_buffer();
+		self
 	}
 
+	pub fn triangle_count(&self) -> usize {
+		self.index_buffer.len() / 3
+	}
+
+	/// Mesh of bare positions, the normals are baked from the faces.
 	pub fn from_vertices_faces(vertices: Vertices, faces: Vec<Idx>) -> Self {
-		Self {
-			vertices,
-			indices: Indices {
-				v: faces,
-				..Default::default()
-			},
+		let indices = Indices {
+			v: faces,
 			..Default::default()
-		}
+		};
+
+		Self::new(vertices, Vec::new(), indices, Vec::new())
+	}
+
+	/// Unique vertices of the faces.
+	pub fn vertex_buffer(&self) -> &[VertexAttributes] {
+		&self.vertex_buffer
+	}
+
+	/// Indices into [`Self::vertex_buffer`], three per triangle.
+	pub fn index_buffer(&self) -> &[u32] {
+		&self.index_buffer
 	}
 
 	pub fn vertex_count(&self) -> usize {
@@ -107,6 +183,10 @@ impl Mesh {
 		!self.uv.is_empty()
 	}
 
+	pub fn has_colors(&self) -> bool {
+		!self.colors.is_empty()
+	}
+
 	fn bake_mesh(
 		vertices: &Vertices,
 		indices: &mut Indices,
@@ -287,3 +367,27 @@ impl Iterator for IterUV<'_> {
 		Some(self.mesh.uv[idx])
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use {super::Mesh, crate::math::Vector3};
+
+	#[test]
+	fn shared_vertices_are_stored_once() {
+		// Quad made of two triangles sharing an edge.
+		let vertices = vec![
+			Vector3::new(0.0, 0.0, 0.0),
+			Vector3::new(1.0, 0.0, 0.0),
+			Vector3::new(1.0, 1.0, 0.0),
+			Vector3::new(0.0, 1.0, 0.0),
+		];
+		let mesh = Mesh::from_vertices_faces(vertices, vec![0, 1, 2, 0, 2, 3]);
+
+		assert_eq!(mesh.vertex_buffer().len(), 4);
+		assert_eq!(mesh.index_buffer(), [0, 1, 2, 0, 2, 3]);
+		assert_eq!(mesh.triangle_count(), 2);
+
+		let normal = mesh.vertex_buffer()[2].normal;
+		assert!((normal - Vector3::new(0.0, 0.0, 1.0)).magnitude() < 1e-5);
+	}
+}
@@ -1,3 +1,4 @@
+mod bounds;
 mod mesh;
 mod shapes;
 mod triangle;
@@ -8,7 +9,7 @@ use {
 	image::Rgb,
 	std::ops::{Add, Mul, Sub},
 };
-pub use {mesh::*, shapes::*, triangle::*, vertex::*};
+pub use {bounds::*, mesh::*, shapes::*, triangle::*, vertex::*};
 
 pub type Idx = usize;
 
@@ -1,4 +1,5 @@
 use crate::{
+	color::Color,
 	geometry::{Mesh, VertexAttributes},
 	math::Vector2,
 };
@@ -19,16 +20,22 @@ impl Iterator for Triangles<'_> {
 		let v_attributes = [0, 1, 2].map(|i| {
 			let index = self.counter + i;
 
-			let v_id = self.mesh.indices.v[index];
-			let n_id = self.mesh.indices.n[index];
-			let uv_id = self.mesh.indices.uv[index];
+			let mesh = self.mesh;
+			let v_id = mesh.indices.v[index];
 
-			let v = self.mesh.vertices[v_id];
-			let n = self.mesh.normals[n_id];
-			let uv = self.mesh.uv[uv_id];
+			// Meshes without uv, like the ones built from bare positions,
+			// have neither uv nor tangents.
+			let n = mesh.indices.n.get(index).map(|&n_id| mesh.normals[n_id]);
+			let uv = mesh.indices.uv.get(index).map(|&uv_id| mesh.uv[uv_id]);
 
-			let tangent = self.mesh.tangents[v_id];
-			let bi_tangent = self.mesh.bi_tangents[v_id];
+			let v = mesh.vertices[v_id];
+			let n = n.unwrap_or_default();
+			let uv = uv.unwrap_or_default();
+
+			let tangent = mesh.tangents.get(v_id).copied().unwrap_or_default();
+			let bi_tangent = mesh.bi_tangents.get(v_id).copied().unwrap_or_default();
+
+			let color = mesh.colors.get(v_id).copied().unwrap_or(Color::WHITE);
 
 			VertexAttributes {
 				position: v,
@@ -36,6 +43,7 @@ impl Iterator for Triangles<'_> {
 				uv,
 				tangent,
 				bi_tangent,
+				color,
 			}
 		});
 
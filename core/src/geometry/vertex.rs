@@ -1,4 +1,7 @@
-use crate::geometry::{BiTangent, Normal, Tangent, UV, Vector3};
+use crate::{
+	color::Color,
+	geometry::{BiTangent, Normal, Tangent, UV, Vector3},
+};
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct VertexAttributes {
@@ -7,6 +10,8 @@ pub struct VertexAttributes {
 	pub uv: UV,
 	pub tangent: Tangent,
 	pub bi_tangent: BiTangent,
+	/// Vertex color, white when the mesh has none.
+	pub color: Color,
 }
 
 impl VertexAttributes {
@@ -34,4 +39,9 @@ impl VertexAttributes {
 	pub fn set_bi_tangent(&mut self, v: BiTangent) {
 		self.bi_tangent = v;
 	}
+
+	#[inline(always)]
+	pub fn set_color(&mut self, v: Color) {
+		self.color = v;
+	}
 }
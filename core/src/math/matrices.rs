@@ -207,6 +207,27 @@ impl Matrix4 {
 		}
 	}
 
+	/// Perspective projection with an infinite far plane mapping depth in
+	/// reverse, the near plane to 1 and infinity to 0. Spreads the float
+	/// precision evenly over the distance instead of crowding it at near.
+	#[inline(always)]
+	pub fn reverse_z_perspective_matrix(
+		fov_rad: f32,
+		aspect: f32,
+		near: f32,
+	) -> Matrix4 {
+		let f = 1.0 / (fov_rad / 2.0).tan();
+
+		Matrix4 {
+			data: [
+				[f / aspect, 0.0, 0.0, 0.0],
+				[0.0, f, 0.0, 0.0],
+				[0.0, 0.0, 0.0, near],
+				[0.0, 0.0, -1.0, 0.0],
+			],
+		}
+	}
+
 	#[inline(always)]
 	pub fn perspective_matrix(
 		fov_rad: f32,
@@ -188,6 +188,20 @@ impl Vector3 {
 			y: self.y,
 		}
 	}
+
+	/// Two unit vectors perpendicular to this unit vector and to each
+	/// other, e.g. a tangent and bi-tangent around a normal.
+	#[inline]
+	pub fn orthonormal_basis(&self) -> (Vector3, Vector3) {
+		let helper = if self.x.abs() < 0.9 {
+			Vector3::UNIT_X
+		} else {
+			Vector3::UNIT_Y
+		};
+
+		let t = helper.cross(self).normalize();
+		(t, self.cross(&t))
+	}
 }
 
 impl Mul for Vector3 {
@@ -293,6 +307,48 @@ impl Mul<f32> for Vector4 {
 	}
 }
 
+impl Mul for Vector4 {
+	type Output = Vector4;
+
+	#[inline(always)]
+	fn mul(self, rhs: Vector4) -> Self::Output {
+		Vector4 {
+			x: self.x * rhs.x,
+			y: self.y * rhs.y,
+			z: self.z * rhs.z,
+			w: self.w * rhs.w,
+		}
+	}
+}
+
+impl Add for Vector4 {
+	type Output = Vector4;
+
+	#[inline(always)]
+	fn add(self, other: Vector4) -> Vector4 {
+		Vector4 {
+			x: self.x + other.x,
+			y: self.y + other.y,
+			z: self.z + other.z,
+			w: self.w + other.w,
+		}
+	}
+}
+
+impl Sub for Vector4 {
+	type Output = Vector4;
+
+	#[inline(always)]
+	fn sub(self, other: Vector4) -> Vector4 {
+		Vector4 {
+			x: self.x - other.x,
+			y: self.y - other.y,
+			z: self.z - other.z,
+			w: self.w - other.w,
+		}
+	}
+}
+
 impl Vector4 {
 	#[inline(always)]
 	pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
@@ -311,3 +367,28 @@ impl From<(Vector3, f32)> for Vector4 {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::Vector3;
+
+	#[test]
+	fn orthonormal_basis_is_perpendicular_to_the_vector() {
+		let vectors = [
+			Vector3::UNIT_X,
+			Vector3::UNIT_Y,
+			Vector3::new(0.0, 0.0, -1.0),
+			Vector3::new(0.3, -0.8, 0.5).normalize(),
+		];
+
+		for n in vectors {
+			let (t, b) = n.orthonormal_basis();
+
+			assert!((t.magnitude() - 1.0).abs() < 1e-5);
+			assert!((b.magnitude() - 1.0).abs() < 1e-5);
+			assert!(t.dot(&n).abs() < 1e-5);
+			assert!(b.dot(&n).abs() < 1e-5);
+			assert!(t.dot(&b).abs() < 1e-5);
+		}
+	}
+}
@@ -1,7 +1,7 @@
 use {
 	pcore::{color::Color, error::PResult, math::Vector3},
 	pixels::{Pixels, SurfaceTexture},
-	prenderer::render,
+	prenderer::{overlay::GridSettings, render},
 	pscene::{
 		assets::registry::{AssetRegistry, MaterialHandle, MeshHandle},
 		global::Scene,
@@ -46,7 +46,7 @@ impl<'f> Engine<'f> {
 			)?,
 			Event::AboutToWait => {
 				update_scene(&mut self.scene, &self.input);
-				self.fps.update();
+				self.fps.update(self.renderer.stats());
 				self.window.request_redraw();
 			}
 			Event::DeviceEvent {
@@ -122,7 +122,9 @@ fn main() -> PResult<()> {
 		SurfaceTexture::new(size.width, size.height, window.clone()),
 	)?;
 
-	let renderer = render::Renderer::new(size.width, size.height);
+	let mut renderer = render::Renderer::new(size.width, size.height);
+	renderer.set_grid(Some(GridSettings::default()));
+	renderer.set_axis_gizmo(true);
 	let input = InputState::default();
 
 	let sphere = pcore::geometry::generate_sphere(1.25, 48, 32);
@@ -137,6 +139,7 @@ fn main() -> PResult<()> {
 		light: Light::default(),
 		assets: AssetRegistry::default(),
 		objects: Vec::new(),
+		instances: Vec::new(),
 	};
 
 	let h_matte = build_matte_material(&mut scene);
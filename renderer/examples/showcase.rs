@@ -1,7 +1,13 @@
 use {
-	pcore::{color::Color, error::PResult, math::Vector3},
+	pcore::{
+		color::Color,
+		error::PResult,
+		math::{Matrix4, Vector3},
+	},
 	pixels::{Pixels, SurfaceTexture},
-	prenderer::render,
+	prenderer::{
+		debug::DebugView, overlay::GridSettings, post::fxaa::FxaaQuality, render,
+	},
 	pscene::{
 		assets::registry::{AssetRegistry, MaterialHandle},
 		global::Scene,
@@ -44,6 +50,12 @@ struct ShowcaseState {
 	materials: [MaterialHandle; 4],
 	active: Mode,
 	tab_down: bool,
+	v_down: bool,
+	b_down: bool,
+	helpers: bool,
+	g_down: bool,
+	o_down: bool,
+	f_down: bool,
 }
 
 impl ShowcaseState {
@@ -52,7 +64,106 @@ impl ShowcaseState {
 			materials,
 			active: Mode::Matte,
 			tab_down: false,
+			v_down: false,
+			b_down: false,
+			helpers: false,
+			g_down: false,
+			o_down: false,
+			f_down: false,
+		}
+	}
+
+	fn update_debug_view(
+		&mut self,
+		input: &InputState,
+		renderer: &mut render::Renderer,
+	) {
+		let v_now = input.is_pressed(Keys::V);
+		if v_now && !self.v_down {
+			let views = DebugView::ALL;
+			let current = views
+				.iter()
+				.position(|view| *view == renderer.debug_view())
+				.unwrap_or(0);
+
+			renderer.set_debug_view(views[(current + 1) % views.len()]);
+		}
+		self.v_down = v_now;
+	}
+
+	fn update_overlays(
+		&mut self,
+		input: &InputState,
+		scene: &Scene,
+		renderer: &mut render::Renderer,
+	) {
+		let object_count = scene.objects.len();
+
+		let g_now = input.is_pressed(Keys::G);
+		if g_now && !self.g_down {
+			let enabled = renderer.grid().is_none();
+			renderer.set_grid(enabled.then(GridSettings::default));
+			renderer.set_axis_gizmo(enabled);
+		}
+		self.g_down = g_now;
+
+		// Cycles the outlined object, wrapping back to no selection.
+		let o_now = input.is_pressed(Keys::O);
+		if o_now && !self.o_down {
+			let selection = match renderer.selection() {
+				None if object_count > 0 => Some(0),
+				Some(i) if i + 1 < object_count => Some(i + 1),
+				_ => None,
+			};
+			renderer.set_selection(selection);
+		}
+		self.o_down = o_now;
+
+		// Cycles the FXAA presets, wrapping back to disabled.
+		let f_now = input.is_pressed(Keys::F);
+		if f_now && !self.f_down {
+			let presets = FxaaQuality::ALL;
+			let next = match renderer.fxaa() {
+				None => Some(presets[0]),
+				Some(quality) => presets
+					.iter()
+					.position(|preset| *preset == quality)
+					.and_then(|i| presets.get(i + 1).copied()),
+			};
+			renderer.set_fxaa(next);
+		}
+		self.f_down = f_now;
+	}
+
+	fn update_helpers(
+		&mut self,
+		input: &InputState,
+		scene: &Scene,
+		renderer: &mut render::Renderer,
+	) {
+		let b_now = input.is_pressed(Keys::B);
+		if b_now && !self.b_down {
+			self.helpers = !self.helpers;
+		}
+		self.b_down = b_now;
+
+		if !self.helpers {
+			return;
 		}
+
+		let debug_draw = renderer.debug_draw();
+		debug_draw.axes(&Matrix4::identity(), 1.0);
+
+		for object in &scene.objects {
+			let object = object.resolve(&scene.assets);
+			let bounds = object.model.mesh.bounds.transform(&object.m_model);
+			debug_draw.sphere(bounds.center, bounds.radius, Color::YELLOW);
+		}
+
+		debug_draw
+			.set_depth_test(false)
+			.arrow(scene.light.position, Vector3::ZERO, Color::WHITE)
+			.set_depth_test(true);
 	}
 
 	fn update(&mut self, input: &InputState, scene: &mut Scene) {
@@ -99,7 +210,20 @@ impl<'f> Engine<'f> {
 			)?,
 			Event::AboutToWait => {
 				update_scene(&mut self.scene, &self.input, &mut self.showcase);
-				self.fps.update();
+				self
+					.showcase
+					.update_debug_view(&self.input, &mut self.renderer);
+				self.showcase.update_overlays(
+					&self.input,
+					&self.scene,
+					&mut self.renderer,
+				);
+				self.showcase.update_helpers(
+					&self.input,
+					&self.scene,
+					&mut self.renderer,
+				);
+				self.fps.update(self.renderer.stats());
 				self.window.request_redraw();
 			}
 			Event::DeviceEvent {
@@ -191,6 +315,7 @@ fn main() -> PResult<()> {
 		light: Light::default(),
 		assets: AssetRegistry::default(),
 		objects: Vec::new(),
+		instances: Vec::new(),
 	};
 
 	let h_albedo = scene.assets.insert_albedo(albedo);
@@ -1,7 +1,7 @@
 use {
 	pcore::{color::Color, error::PResult, math::Vector3},
 	pixels::{Pixels, SurfaceTexture},
-	prenderer::render,
+	prenderer::{overlay::GridSettings, render},
 	pscene::{
 		assets::{load_mesh_file, registry::AssetRegistry},
 		// color::Color,
@@ -39,7 +39,9 @@ fn main() -> PResult<()> {
 		SurfaceTexture::new(size.width, size.height, window.clone()),
 	)?;
 
-	let renderer = render::Renderer::new(size.width, size.height);
+	let mut renderer = render::Renderer::new(size.width, size.height);
+	renderer.set_grid(Some(GridSettings::default()));
+	renderer.set_axis_gizmo(true);
 	let input = InputState::default();
 
 	let cube_mesh = load_mesh_file("./assets/meshes/cube-local.obj").unwrap();
@@ -56,6 +58,7 @@ fn main() -> PResult<()> {
 		light: Light::default(),
 		assets: AssetRegistry::default(),
 		objects: Vec::new(),
+		instances: Vec::new(),
 	};
 
 	let h_albedo = scene.assets.insert_albedo(albedo);
@@ -29,7 +29,7 @@ impl<'f> Engine<'f> {
 			)?,
 			Event::AboutToWait => {
 				update_scene(&mut self.scene, &self.input);
-				self.fps.update();
+				self.fps.update(self.renderer.stats());
 				self.window.request_redraw();
 			}
 			Event::DeviceEvent {
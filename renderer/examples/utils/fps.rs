@@ -1,3 +1,4 @@
+use prenderer::stats::RenderStats;
 use std::time::{Duration, Instant};
 
 pub struct FPSCounter {
@@ -25,7 +26,7 @@ impl FPSCounter {
         }
     }
 
-    pub fn update(&mut self) {
+    pub fn update(&mut self, stats: &RenderStats) {
         self.frames += 1;
         let elapsed = self.last_sampled_instant.elapsed();
 
@@ -33,17 +34,17 @@ impl FPSCounter {
             self.fps = self.frames as f32 / elapsed.as_secs_f32();
             self.ms_per_frame = elapsed.as_secs_f32() * 1000.0 / self.frames as f32;
 
-            self.print_stats();
+            self.print_stats(stats);
 
             self.frames = 0;
             self.last_sampled_instant = Instant::now();
         }
     }
 
-    fn print_stats(&self) {
+    fn print_stats(&self, stats: &RenderStats) {
         print!(
-            "\rFPS: {:>6.1} | FrameTime: {:>6.2}ms",
-            self.fps, self.ms_per_frame
+            "\rFPS: {:>6.1} | FrameTime: {:>6.2}ms | {}",
+            self.fps, self.ms_per_frame, stats
         );
         use std::io::{self, Write};
         io::stdout().flush().unwrap();
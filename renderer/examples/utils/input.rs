@@ -18,6 +18,11 @@ pub enum Keys {
     Left,
     Right,
     Tab,
+    V,
+    B,
+    G,
+    O,
+    F,
 }
 
 impl TryFrom<winit::keyboard::KeyCode> for Keys {
@@ -36,6 +41,11 @@ impl TryFrom<winit::keyboard::KeyCode> for Keys {
             winit::keyboard::KeyCode::ArrowLeft => Ok(Keys::Left),
             winit::keyboard::KeyCode::ArrowRight => Ok(Keys::Right),
             winit::keyboard::KeyCode::Tab => Ok(Keys::Tab),
+            winit::keyboard::KeyCode::KeyV => Ok(Keys::V),
+            winit::keyboard::KeyCode::KeyB => Ok(Keys::B),
+            winit::keyboard::KeyCode::KeyG => Ok(Keys::G),
+            winit::keyboard::KeyCode::KeyO => Ok(Keys::O),
+            winit::keyboard::KeyCode::KeyF => Ok(Keys::F),
             _ => Err(()),
         }
     }
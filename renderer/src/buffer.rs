@@ -1,16 +1,56 @@
 // use pscene::color::Color;
-use pcore::color::Color;
+use {
+	crate::{
+		raster::VertexCache,
+		shaders::{Surface, uniform::PrimitiveUniforms},
+		view::Rect,
+	},
+	pcore::color::Color,
+};
 
 const DEFAULT_BG_COLOR: u8 = 77;
 const DEFAULT_DEPTH: f32 = f32::INFINITY;
+const REVERSE_Z_DEPTH: f32 = 0.0;
 
 pub type FrameBuffer = Vec<u8>;
+pub type ColorBuffer = Vec<Color>;
 pub type DepthBuffer = Vec<f32>;
+pub type OverdrawBuffer = Vec<u16>;
+pub type GBuffer = Vec<Option<Surface>>;
+pub type OcclusionBuffer = Vec<f32>;
+pub type IdBuffer = Vec<Option<PrimitiveUniforms>>;
+pub type StencilBuffer = Vec<u8>;
 
 #[derive(Default)]
 pub struct Buffers {
+	/// Displayed pixels, sRGB encoded. Overlays are drawn straight into it.
 	pub f_buffer: FrameBuffer,
 	pub z_buffer: DepthBuffer,
+
+	/// Linear and unclamped color of every pixel, lighting and the post
+	/// effects work on it before it is encoded into `f_buffer`.
+	pub hdr: ColorBuffer,
+
+	/// Per pixel fragment count, only allocated while tracking overdraw.
+	pub overdraw: OverdrawBuffer,
+
+	/// Per pixel surface attributes, only allocated for deferred shading.
+	pub g_buffer: GBuffer,
+
+	/// Per pixel ambient occlusion, only allocated while SSAO is enabled.
+	pub ao: OcclusionBuffer,
+
+	/// Per pixel object and triangle index, only allocated for picking.
+	pub ids: IdBuffer,
+
+	/// Per pixel stencil value, only allocated while a draw uses it.
+	pub stencil: StencilBuffer,
+
+	/// Transformed vertices of the view being rendered.
+	pub vertex_cache: VertexCache,
+
+	/// Value the depth buffer is cleared to, the farthest possible depth.
+	depth_clear: f32,
 }
 
 impl Buffers {
@@ -19,18 +59,174 @@ impl Buffers {
 		Self {
 			f_buffer: vec![DEFAULT_BG_COLOR; (size * 4) as usize],
 			z_buffer: vec![DEFAULT_DEPTH; size as usize],
+			hdr: vec![background(); size as usize],
+			overdraw: Vec::new(),
+			g_buffer: Vec::new(),
+			ao: Vec::new(),
+			ids: Vec::new(),
+			stencil: Vec::new(),
+			vertex_cache: VertexCache::default(),
+			depth_clear: DEFAULT_DEPTH,
 		}
 	}
 
 	pub fn reset(&mut self) {
 		self.f_buffer.fill(DEFAULT_BG_COLOR);
-		self.z_buffer.fill(DEFAULT_DEPTH);
+		self.z_buffer.fill(self.depth_clear);
+		self.hdr.fill(background());
+		self.overdraw.fill(0);
+		self.g_buffer.fill(None);
+		self.ao.fill(1.0);
+		self.ids.fill(None);
+		self.stencil.fill(0);
+	}
+
+	/// Resets the pixels of `rect` only, for a target `stride` pixels wide.
+	/// Buffers which are not tracked are skipped.
+	pub fn clear_rect(&mut self, rect: &Rect, stride: u32) {
+		for row in rect.rows(stride) {
+			self.f_buffer[row.start * 4..row.end * 4].fill(DEFAULT_BG_COLOR);
+			self.z_buffer[row.clone()].fill(self.depth_clear);
+			self.hdr[row.clone()].fill(background());
+
+			if let Some(overdraw) = self.overdraw.get_mut(row.clone()) {
+				overdraw.fill(0);
+			}
+
+			if let Some(g_buffer) = self.g_buffer.get_mut(row.clone()) {
+				g_buffer.fill(None);
+			}
+
+			if let Some(ao) = self.ao.get_mut(row.clone()) {
+				ao.fill(1.0);
+			}
+
+			if let Some(ids) = self.ids.get_mut(row.clone()) {
+				ids.fill(None);
+			}
+
+			if let Some(stencil) = self.stencil.get_mut(row) {
+				stencil.fill(0);
+			}
+		}
 	}
 
 	pub fn resize(&mut self, width: u32, height: u32) {
 		let size = width * height;
 		self.f_buffer.resize((size * 4) as usize, DEFAULT_BG_COLOR);
-		self.z_buffer.resize(size as usize, DEFAULT_DEPTH);
+		self.z_buffer.resize(size as usize, self.depth_clear);
+		self.hdr.resize(size as usize, background());
+
+		if !self.overdraw.is_empty() {
+			self.overdraw.resize(size as usize, 0);
+		}
+
+		if !self.g_buffer.is_empty() {
+			self.g_buffer.resize(size as usize, None);
+		}
+
+		if !self.ao.is_empty() {
+			self.ao.resize(size as usize, 1.0);
+		}
+
+		if !self.ids.is_empty() {
+			self.ids.resize(size as usize, None);
+		}
+
+		if !self.stencil.is_empty() {
+			self.stencil.resize(size as usize, 0);
+		}
+	}
+
+	/// Clears depth for a reverse-Z projection, 0 is the farthest depth.
+	pub fn set_reverse_z(&mut self, enabled: bool) {
+		self.depth_clear = if enabled {
+			REVERSE_Z_DEPTH
+		} else {
+			DEFAULT_DEPTH
+		};
+	}
+
+	pub fn depth_clear(&self) -> f32 {
+		self.depth_clear
+	}
+
+	/// Allocates or releases the overdraw counters.
+	pub fn track_overdraw(&mut self, enabled: bool) {
+		if enabled {
+			self.overdraw.resize(self.z_buffer.len(), 0);
+		} else if !self.overdraw.is_empty() {
+			self.overdraw = Vec::new();
+		}
+	}
+
+	/// Allocates or releases the G-buffer.
+	pub fn track_g_buffer(&mut self, enabled: bool) {
+		if enabled {
+			self.g_buffer.resize(self.z_buffer.len(), None);
+		} else if !self.g_buffer.is_empty() {
+			self.g_buffer = Vec::new();
+		}
+	}
+
+	/// Allocates or releases the ambient occlusion buffer.
+	pub fn track_ao(&mut self, enabled: bool) {
+		if enabled {
+			self.ao.resize(self.z_buffer.len(), 1.0);
+		} else if !self.ao.is_empty() {
+			self.ao = Vec::new();
+		}
+	}
+
+	/// Allocates or releases the object and triangle id buffer.
+	pub fn track_ids(&mut self, enabled: bool) {
+		if enabled {
+			self.ids.resize(self.z_buffer.len(), None);
+		} else if !self.ids.is_empty() {
+			self.ids = Vec::new();
+		}
+	}
+
+	/// Allocates or releases the stencil buffer.
+	pub fn track_stencil(&mut self, enabled: bool) {
+		if enabled {
+			self.stencil.resize(self.z_buffer.len(), 0);
+		} else if !self.stencil.is_empty() {
+			self.stencil = Vec::new();
+		}
+	}
+
+	/// Stencil value of the pixel, 0 when the stencil is not tracked.
+	#[inline(always)]
+	pub fn stencil(&self, offset: usize) -> u8 {
+		self.stencil.get(offset).copied().unwrap_or(0)
+	}
+
+	#[inline(always)]
+	pub fn put_stencil(&mut self, offset: usize, value: u8) {
+		if let Some(slot) = self.stencil.get_mut(offset) {
+			*slot = value;
+		}
+	}
+
+	#[inline(always)]
+	pub fn put_id(&mut self, offset: usize, id: PrimitiveUniforms) {
+		if let Some(slot) = self.ids.get_mut(offset) {
+			*slot = Some(id);
+		}
+	}
+
+	/// Ambient occlusion of the pixel, fully unoccluded without SSAO.
+	#[inline(always)]
+	pub fn occlusion(&self, offset: usize) -> f32 {
+		self.ao.get(offset).copied().unwrap_or(1.0)
+	}
+
+	#[inline(always)]
+	pub fn count_fragment(&mut self, offset: usize) {
+		if let Some(count) = self.overdraw.get_mut(offset) {
+			*count = count.saturating_add(1);
+		}
 	}
 
 	pub fn mut_buffers(&mut self) -> (&mut FrameBuffer, &mut DepthBuffer) {
@@ -39,27 +235,33 @@ impl Buffers {
 
 	pub fn get_cursor(&mut self, offset: usize) -> Cursor {
 		assert!(
-			offset * 4 < self.f_buffer.len() && offset < self.z_buffer.len(),
-			"Memory out of bounds in buffers, offset={}, zbuf={}, fbuf={}",
+			offset < self.hdr.len() && offset < self.z_buffer.len(),
+			"Memory out of bounds in buffers, offset={}, zbuf={}, hdr={}",
 			offset,
 			self.z_buffer.len(),
-			self.f_buffer.len()
+			self.hdr.len()
 		);
 
 		unsafe {
 			Cursor {
-				f_buffer: self.f_buffer.as_mut_ptr().add(offset * 4),
+				hdr: self.hdr.as_mut_ptr().add(offset),
 				z_buffer: self.z_buffer.as_mut_ptr().add(offset),
 			}
 		}
 	}
 }
 
-pub type RawFrameBuffer = *mut u8;
+/// Linear color of the cleared pixels.
+#[inline]
+fn background() -> Color {
+	Color::from_srgba8([DEFAULT_BG_COLOR; 4])
+}
+
+pub type RawColorBuffer = *mut Color;
 pub type RawZBuffer = *mut f32;
 
 pub struct Cursor {
-	f_buffer: RawFrameBuffer,
+	hdr: RawColorBuffer,
 	z_buffer: RawZBuffer,
 }
 
@@ -68,7 +270,7 @@ impl Cursor {
 	pub fn increment(&mut self, offset: usize) {
 		unsafe {
 			self.z_buffer = self.z_buffer.add(offset);
-			self.f_buffer = self.f_buffer.add(offset * 4);
+			self.hdr = self.hdr.add(offset);
 		}
 	}
 
@@ -84,10 +286,11 @@ impl Cursor {
 		}
 	}
 
+	/// Writes the linear color of the pixel.
 	#[inline(always)]
 	pub fn put_pixel(&self, color: Color) {
 		unsafe {
-			*(self.f_buffer as *mut u32) = u32::from_le_bytes(color.to_rgba8())
+			*self.hdr = color;
 		}
 	}
 
@@ -95,7 +298,7 @@ impl Cursor {
 	pub fn step(&mut self) {
 		unsafe {
 			self.z_buffer = self.z_buffer.add(1);
-			self.f_buffer = self.f_buffer.add(4);
+			self.hdr = self.hdr.add(1);
 		}
 	}
 }
@@ -0,0 +1,158 @@
+use {
+	crate::{
+		buffer::Buffers,
+		shaders::{Varyings, uniform::GlobalUniforms},
+	},
+	pcore::{color::Color, math::Vector3},
+};
+
+/// Debug visualization that replaces the final shaded color.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugView {
+	/// Regular shading, no debug output.
+	#[default]
+	Off,
+
+	/// Interpolated world space normal, remapped from [-1, 1] to [0, 1].
+	Normals,
+
+	/// Interpolated world space tangent, remapped from [-1, 1] to [0, 1].
+	Tangents,
+
+	/// Interpolated world space bi-tangent, remapped from [-1, 1] to [0, 1].
+	BiTangents,
+
+	/// Texture coordinates as red (u) and green (v).
+	UV,
+
+	/// Selected mip level as a heat map, blue is the base level.
+	MipLevel,
+
+	/// View space depth linearized between the camera near and far planes.
+	Depth,
+
+	/// Number of fragments written per pixel as a heat map.
+	Overdraw,
+
+	/// Flat color per triangle, hashed from the object and triangle index.
+	Triangles,
+}
+
+impl DebugView {
+	pub const ALL: [DebugView; 9] = [
+		DebugView::Off,
+		DebugView::Normals,
+		DebugView::Tangents,
+		DebugView::BiTangents,
+		DebugView::UV,
+		DebugView::MipLevel,
+		DebugView::Depth,
+		DebugView::Overdraw,
+		DebugView::Triangles,
+	];
+}
+
+/// Heat map ramp shared by the mip level and overdraw views.
+const HEAT: [Color; 6] = [
+	Color::BLUE,
+	Color::CYAN,
+	Color::GREEN,
+	Color::YELLOW,
+	Color::RED,
+	Color::MAGENTA,
+];
+
+/// Computes the debug color of a fragment which passed the depth test.
+/// The `w` is the recovered clip space w which is the view space depth
+/// for a perspective projection.
+pub fn shade_fragment(
+	view: DebugView,
+	varyings: &Varyings,
+	w: f32,
+	uniforms: &GlobalUniforms,
+) -> Color {
+	let color = match view {
+		DebugView::Normals => direction_color(varyings.normal),
+		DebugView::Tangents => direction_color(varyings.tangent),
+		DebugView::BiTangents => direction_color(varyings.bi_tangent),
+		DebugView::UV => {
+			let uv = varyings.uv;
+			Color::new_rgb(uv.x - uv.x.floor(), uv.y - uv.y.floor(), 0.0)
+		}
+		DebugView::MipLevel => heat_color(uniforms.lods.albedo.unwrap_or(0.0)),
+		DebugView::Depth => {
+			let near = uniforms.camera.near;
+			let far = uniforms.camera.far;
+
+			Color::new_rgb_splat(1.0 - (w - near) / (far - near))
+		}
+		DebugView::Triangles => {
+			let primitive = uniforms.primitive;
+			hash_color(primitive.object as u32, primitive.triangle as u32)
+		}
+		// Overdraw is resolved once all the fragments are counted, see
+		// `resolve_overdraw`.
+		DebugView::Overdraw | DebugView::Off => Color::BLACK,
+	};
+
+	// The debug colors are meant to be displayed as they are, decoding them
+	// cancels out the encode of the output.
+	color.to_linear()
+}
+
+/// Replaces the pixels of the view with the overdraw heat map, pixels
+/// which were never written keep the background color. Runs on the
+/// encoded frame.
+pub fn resolve_overdraw(buffers: &mut Buffers, uniforms: &GlobalUniforms) {
+	let Buffers {
+		f_buffer, overdraw, ..
+	} = buffers;
+
+	let rows = uniforms.screen.scissor.rows(uniforms.screen.width as u32);
+
+	for i in rows.flatten() {
+		let count = overdraw[i];
+
+		if count == 0 {
+			continue;
+		}
+
+		let color = heat_color((count - 1) as f32);
+		f_buffer[i * 4..i * 4 + 4].copy_from_slice(&color.to_rgba8());
+	}
+}
+
+#[inline]
+fn direction_color(v: Vector3) -> Color {
+	let n = v.normalize();
+	Color::new_rgb(n.x * 0.5 + 0.5, n.y * 0.5 + 0.5, n.z * 0.5 + 0.5)
+}
+
+#[inline]
+fn heat_color(level: f32) -> Color {
+	let max = (HEAT.len() - 1) as f32;
+	let level = level.clamp(0.0, max);
+
+	let i0 = level.floor() as usize;
+	let i1 = (i0 + 1).min(HEAT.len() - 1);
+
+	pcore::math::lerp(HEAT[i0], HEAT[i1], level.fract())
+}
+
+#[inline]
+fn hash_color(object: u32, triangle: u32) -> Color {
+	// Integer hash (lowbias32), good enough to keep neighbouring
+	// triangles visually distinct.
+	let mut h = triangle ^ object.wrapping_mul(0x9E37_79B9);
+	h ^= h >> 16;
+	h = h.wrapping_mul(0x7FEB_352D);
+	h ^= h >> 15;
+	h = h.wrapping_mul(0x846C_A68B);
+	h ^= h >> 16;
+
+	Color::new_rgb(
+		(h & 0xFF) as f32 / 255.0,
+		((h >> 8) & 0xFF) as f32 / 255.0,
+		((h >> 16) & 0xFF) as f32 / 255.0,
+	)
+}
@@ -0,0 +1,443 @@
+use {
+	crate::{buffer::Buffers, draw, raster, shaders::uniform::GlobalUniforms},
+	pcore::{
+		color::Color,
+		math::{Matrix4, Vector3, Vector4},
+	},
+	pscene::{material::DepthCompare, object::ObjectRef},
+	std::f32::consts::TAU,
+};
+
+/// Segments used to approximate a circle.
+const CIRCLE_SEGMENTS: usize = 32;
+
+/// NDC depth of the far corners of a reverse-Z frustum, a thousand times
+/// the near distance away.
+pub const REVERSE_Z_FAR_DEPTH: f32 = 1e-3;
+
+/// World space line collected by `DebugDraw`.
+#[derive(Clone, Copy)]
+pub struct DebugLine {
+	pub start: Vector3,
+	pub end: Vector3,
+	pub color: Color,
+	pub depth_test: bool,
+}
+
+/// Immediate-mode collector of helper geometry, everything is built out
+/// of lines and drawn over the frame after the main pass. The collected
+/// lines are dropped once the frame is rendered.
+pub struct DebugDraw {
+	lines: Vec<DebugLine>,
+	depth_test: bool,
+}
+
+impl Default for DebugDraw {
+	fn default() -> Self {
+		Self {
+			lines: Vec::new(),
+			depth_test: true,
+		}
+	}
+}
+
+impl DebugDraw {
+	/// Whether the primitives added from now on are hidden by the scene,
+	/// enabled by default.
+	pub fn set_depth_test(&mut self, enabled: bool) -> &mut Self {
+		self.depth_test = enabled;
+		self
+	}
+
+	pub fn line(
+		&mut self,
+		start: Vector3,
+		end: Vector3,
+		color: Color,
+	) -> &mut Self {
+		self.lines.push(DebugLine {
+			start,
+			end,
+			color,
+			depth_test: self.depth_test,
+		});
+		self
+	}
+
+	/// Line ending with a four sided head sized relative to its length.
+	pub fn arrow(
+		&mut self,
+		from: Vector3,
+		to: Vector3,
+		color: Color,
+	) -> &mut Self {
+		self.line(from, to, color);
+
+		let dir = to - from;
+		let length = dir.magnitude();
+
+		if length <= f32::EPSILON {
+			return self;
+		}
+
+		let (t, b) = (dir * (1.0 / length)).orthonormal_basis();
+		let head = length * 0.15;
+		let base = to - dir * (head / length);
+
+		for side in [t, t * -1.0, b, b * -1.0] {
+			self.line(to, base + side * (head * 0.5), color);
+		}
+
+		self
+	}
+
+	/// Axis aligned box between `min` and `max`.
+	pub fn aabb(
+		&mut self,
+		min: Vector3,
+		max: Vector3,
+		color: Color,
+	) -> &mut Self {
+		let corners = [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+			Vector3::new(
+				if i & 1 == 0 { min.x } else { max.x },
+				if i & 2 == 0 { min.y } else { max.y },
+				if i & 4 == 0 { min.z } else { max.z },
+			)
+		});
+
+		self.box_edges(corners, color)
+	}
+
+	/// Sphere drawn as its three axis aligned great circles.
+	pub fn sphere(
+		&mut self,
+		center: Vector3,
+		radius: f32,
+		color: Color,
+	) -> &mut Self {
+		let axes = [Vector3::UNIT_X, Vector3::UNIT_Y, Vector3::UNIT_Z];
+
+		for i in 0..3 {
+			let u = axes[i] * radius;
+			let v = axes[(i + 1) % 3] * radius;
+
+			self.circle(center, u, v, color);
+		}
+
+		self
+	}
+
+	/// Frustum of a view projection matrix, e.g. the one of a camera.
+	/// `reverse_z` tells whether the projection is the reverse-Z one, whose
+	/// far plane at infinity is drawn at `REVERSE_Z_FAR_DEPTH`.
+	pub fn frustum(
+		&mut self,
+		m_view_projection: &Matrix4,
+		reverse_z: bool,
+		color: Color,
+	) -> &mut Self {
+		let inv = m_view_projection.inverse();
+
+		let (near, far) = if reverse_z {
+			(1.0, REVERSE_Z_FAR_DEPTH)
+		} else {
+			(-1.0, 1.0)
+		};
+
+		let corners = [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+			let ndc = Vector4::new(
+				if i & 1 == 0 { -1.0 } else { 1.0 },
+				if i & 2 == 0 { -1.0 } else { 1.0 },
+				if i & 4 == 0 { near } else { far },
+				1.0,
+			);
+
+			let v = inv * ndc;
+			v.xyz() * (1.0 / v.w)
+		});
+
+		self.box_edges(corners, color)
+	}
+
+	/// Red, green and blue lines along the x, y and z axes of `transform`.
+	pub fn axes(&mut self, transform: &Matrix4, size: f32) -> &mut Self {
+		let origin = (*transform * Vector4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+
+		let axes = [
+			(Vector3::UNIT_X, Color::RED),
+			(Vector3::UNIT_Y, Color::GREEN),
+			(Vector3::UNIT_Z, Color::BLUE),
+		];
+
+		for (axis, color) in axes {
+			let tip = (*transform * Vector4::from((axis * size, 1.0))).xyz();
+			self.line(origin, tip, color);
+		}
+
+		self
+	}
+
+	/// Small three dimensional cross marking a point.
+	pub fn marker(
+		&mut self,
+		position: Vector3,
+		size: f32,
+		color: Color,
+	) -> &mut Self {
+		let half = size * 0.5;
+
+		for axis in [Vector3::UNIT_X, Vector3::UNIT_Y, Vector3::UNIT_Z] {
+			self.line(position - axis * half, position + axis * half, color);
+		}
+
+		self
+	}
+
+	/// Vertex normals of the object in world space.
+	pub fn normals(
+		&mut self,
+		object: ObjectRef<'_>,
+		length: f32,
+		color: Color,
+	) -> &mut Self {
+		for vertex in object.model.mesh.vertex_buffer() {
+			let position =
+				(object.m_model * Vector4::from((vertex.position, 1.0))).xyz();
+			let normal = (object.m_normal * Vector4::from((vertex.normal, 0.0)))
+				.xyz()
+				.normalize();
+
+			self.line(position, position + normal * length, color);
+		}
+
+		self
+	}
+
+	pub fn lines(&self) -> &[DebugLine] {
+		&self.lines
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.lines.is_empty()
+	}
+
+	pub fn clear(&mut self) {
+		self.lines.clear();
+	}
+
+	/// Rasterizes the collected lines over the frame buffer.
+	pub fn render(&self, buffers: &mut Buffers, uniforms: &GlobalUniforms) {
+		for line in &self.lines {
+			let start = uniforms.m_view_projection * Vector4::from((line.start, 1.0));
+			let end = uniforms.m_view_projection * Vector4::from((line.end, 1.0));
+
+			let Some((start, end)) = clip_line(start, end) else {
+				continue;
+			};
+
+			let to_screen = |v: Vector4| {
+				let inv_w = 1.0 / v.w;
+				let r = raster::clip_to_screen(&(v * inv_w), &uniforms.screen);
+
+				(r.s.x as i32, r.s.y as i32, r.z)
+			};
+
+			draw::draw_line(
+				buffers,
+				&uniforms.screen,
+				to_screen(start),
+				to_screen(end),
+				line.color,
+				line
+					.depth_test
+					.then(|| uniforms.depth.resolve(DepthCompare::LessEqual)),
+			);
+		}
+	}
+
+	fn circle(&mut self, center: Vector3, u: Vector3, v: Vector3, color: Color) {
+		let point = |i: usize| {
+			let (sin, cos) = (i as f32 / CIRCLE_SEGMENTS as f32 * TAU).sin_cos();
+			center + u * cos + v * sin
+		};
+
+		for i in 0..CIRCLE_SEGMENTS {
+			self.line(point(i), point(i + 1), color);
+		}
+	}
+
+	/// Edges of a box whose corners are indexed by the x, y and z bits.
+	fn box_edges(&mut self, corners: [Vector3; 8], color: Color) -> &mut Self {
+		for i in 0..8 {
+			for bit in [1, 2, 4] {
+				if i & bit == 0 {
+					self.line(corners[i], corners[i | bit], color);
+				}
+			}
+		}
+
+		self
+	}
+}
+
+/// Clips a clip space segment against the view volume, keeping the
+/// rasterized line inside the screen and in front of the camera.
+fn clip_line(a: Vector4, b: Vector4) -> Option<(Vector4, Vector4)> {
+	// Signed distances to the six planes, positive inside.
+	let distances = |v: Vector4| {
+		[
+			v.w + v.x,
+			v.w - v.x,
+			v.w + v.y,
+			v.w - v.y,
+			v.w + v.z,
+			v.w - v.z,
+		]
+	};
+
+	let da = distances(a);
+	let db = distances(b);
+
+	let (mut t0, mut t1) = (0.0_f32, 1.0_f32);
+
+	for (da, db) in da.into_iter().zip(db) {
+		if da < 0.0 && db < 0.0 {
+			return None;
+		}
+
+		if da < 0.0 {
+			t0 = t0.max(da / (da - db));
+		} else if db < 0.0 {
+			t1 = t1.min(da / (da - db));
+		}
+	}
+
+	if t0 > t1 {
+		return None;
+	}
+
+	let d = b - a;
+	Some((a + d * t0, a + d * t1))
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::{DebugDraw, clip_line},
+		pcore::{
+			color::Color,
+			math::{Vector3, Vector4},
+		},
+		pscene::camera::{Camera, FORWARD},
+	};
+
+	#[test]
+	fn segment_outside_a_plane_is_dropped() {
+		let a = Vector4::new(2.0, 0.0, 0.0, 1.0);
+		let b = Vector4::new(3.0, 0.5, 0.0, 1.0);
+
+		assert!(clip_line(a, b).is_none());
+
+		// Both ends behind the camera.
+		let a = Vector4::new(0.0, 0.0, 0.0, -1.0);
+		let b = Vector4::new(0.0, 0.0, 0.5, -2.0);
+
+		assert!(clip_line(a, b).is_none());
+	}
+
+	#[test]
+	fn straddling_segment_is_clamped_to_the_plane() {
+		let a = Vector4::new(0.0, 0.0, 0.0, 1.0);
+		let b = Vector4::new(2.0, 1.0, 0.0, 1.0);
+
+		let (start, end) = clip_line(a, b).unwrap();
+
+		assert_eq!([start.x, start.y], [0.0, 0.0]);
+		assert!((end.x - 1.0).abs() < 1e-6);
+		assert!((end.y - 0.5).abs() < 1e-6);
+
+		// Clipped on both ends, the inner part is kept.
+		let (start, end) = clip_line(Vector4::new(-3.0, 0.0, 0.0, 1.0), b).unwrap();
+
+		assert!((start.x + 1.0).abs() < 1e-6);
+		assert!((end.x - 1.0).abs() < 1e-6);
+	}
+
+	#[test]
+	fn box_has_twelve_axis_aligned_edges() {
+		let mut debug_draw = DebugDraw::default();
+		debug_draw.aabb(
+			Vector3::splat(-1.0),
+			Vector3::new(1.0, 2.0, 3.0),
+			Color::RED,
+		);
+
+		let lines = debug_draw.lines();
+		assert_eq!(lines.len(), 12);
+
+		// Every edge changes one coordinate, four edges along each axis.
+		let mut per_axis = [0; 3];
+
+		for line in lines {
+			let d = line.end - line.start;
+			let changed = [d.x, d.y, d.z].map(|c| c != 0.0);
+
+			assert_eq!(changed.iter().filter(|&&c| c).count(), 1);
+			per_axis[changed.iter().position(|&c| c).unwrap()] += 1;
+		}
+
+		assert_eq!(per_axis, [4, 4, 4]);
+	}
+
+	/// Distances along the view direction of the frustum corners.
+	fn frustum_depths(reverse_z: bool) -> Vec<f32> {
+		let camera = Camera::new(Vector3::ZERO);
+
+		let m_projection = if reverse_z {
+			camera.get_reverse_z_projection_matrix(1.0)
+		} else {
+			camera.get_projection_matrix(1.0)
+		};
+
+		let mut debug_draw = DebugDraw::default();
+		debug_draw.frustum(
+			&(m_projection * camera.get_view_matrix()),
+			reverse_z,
+			Color::GREEN,
+		);
+
+		assert_eq!(debug_draw.lines().len(), 12);
+
+		debug_draw
+			.lines()
+			.iter()
+			.flat_map(|line| [line.start, line.end])
+			.map(|p| p.dot(&camera.basis[FORWARD]))
+			.collect()
+	}
+
+	#[test]
+	fn frustum_spans_the_depth_range() {
+		let camera = Camera::new(Vector3::ZERO);
+
+		let depths = frustum_depths(false);
+		let (near, far) = depths
+			.iter()
+			.fold((f32::MAX, f32::MIN), |(lo, hi), &d| (lo.min(d), hi.max(d)));
+
+		assert!((near - camera.near).abs() < 1e-3);
+		assert!((far - camera.far).abs() < 1e-1);
+	}
+
+	#[test]
+	fn reverse_z_frustum_stays_finite() {
+		let camera = Camera::new(Vector3::ZERO);
+
+		let depths = frustum_depths(true);
+		assert!(depths.iter().all(|d| d.is_finite()));
+
+		let far = depths.iter().fold(f32::MIN, |hi, &d| hi.max(d));
+		assert!((far - camera.near * 1000.0).abs() < 1.0);
+	}
+}
@@ -0,0 +1,61 @@
+use crate::{
+	buffer::Buffers,
+	shaders::{
+		DS, Surface,
+		uniform::{GlobalUniforms, LightUniforms},
+	},
+};
+
+/// Shading pipeline used by the renderer.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pipeline {
+	/// Every fragment passing the depth test is shaded immediately.
+	#[default]
+	Forward,
+
+	/// Rasterization fills the G-buffer and lighting runs once per
+	/// visible pixel afterwards.
+	Deferred,
+}
+
+/// Lights every pixel of the view stored in the G-buffer, accumulating
+/// the contribution of all the lights. Returns the number of lit pixels.
+pub fn lighting_pass<S>(
+	buffers: &mut Buffers,
+	shader: &S,
+	lights: &[LightUniforms],
+	uniforms: &GlobalUniforms,
+) -> u64
+where
+	S: DS,
+{
+	let Buffers {
+		hdr, g_buffer, ao, ..
+	} = buffers;
+
+	let mut lit = 0;
+
+	let rows = uniforms.screen.scissor.rows(uniforms.screen.width as u32);
+
+	for i in rows.flatten() {
+		let Some(surface) = &g_buffer[i] else {
+			continue;
+		};
+
+		// Occlusion is resolved after the geometry pass, so it is applied
+		// here rather than when the surface is written.
+		let surface = &Surface {
+			ambient: surface.ambient * ao.get(i).copied().unwrap_or(1.0),
+			..*surface
+		};
+
+		let color = lights.iter().fold(surface.emissive, |acc, light| {
+			acc + shader.shade_light(surface, light, uniforms)
+		});
+
+		hdr[i] = color;
+		lit += 1;
+	}
+
+	lit
+}
@@ -4,33 +4,42 @@
 use {
 	crate::{
 		buffer::Buffers,
+		debug::DebugView,
+		raster,
 		render::{Renderer, WinSize},
 		shaders::{
 			FS, VS,
-			uniform::{
-				CameraUniforms, GlobalUniforms, LOD, LightUniforms, ScreenUniforms,
-			},
+			uniform::{GlobalUniforms, ScreenUniforms},
 		},
+		stats::RenderStats,
+		view::View,
+	},
+	pcore::{
+		color::Color,
+		geometry::{Frustum, Mesh},
+		math::{Matrix4, Vector3, Vector4},
+	},
+	pscene::{
+		camera::Camera,
+		global::Scene,
+		material::DepthCompare,
+		model::ModelRef,
+		object::{InstanceRef, ObjectRef},
 	},
-	pcore::math::{Matrix4, Vector3},
-	pscene::{global::Scene, object::ObjectRef},
 };
 
-pub fn draw_line<T>(
-	mut frame: T,
-	depth_buffer: &mut [f32],
-	w: i32,
-	h: i32,
-	x0: i32,
-	y0: i32,
-	z0: f32,
-	x1: i32,
-	y1: i32,
-	z1: f32,
-) where
-	T: AsMut<[u8]>,
-{
-	let frame = frame.as_mut();
+/// Draws a screen space line between two `(x, y, z)` points, clipped to
+/// the scissor. The depth is interpolated linearly which is exact for NDC
+/// depth, it is only tested when a compare function is given and never
+/// written so overlays do not hide each other.
+pub fn draw_line(
+	buffers: &mut Buffers,
+	screen: &ScreenUniforms,
+	(x0, y0, z0): (i32, i32, f32),
+	(x1, y1, z1): (i32, i32, f32),
+	color: Color,
+	depth_test: Option<DepthCompare>,
+) {
 	let dx = (x1 - x0).abs();
 	let dy = -(y1 - y0).abs();
 	let sx = if x0 < x1 { 1 } else { -1 };
@@ -39,23 +48,26 @@ pub fn draw_line<T>(
 	let mut x = x0;
 	let mut y = y0;
 
-	let length = ((x1 - x0).abs().max((y1 - y0).abs())) as f32;
+	let w = screen.width as i32;
+	let scissor = &screen.scissor;
+
+	let length = dx.max(-dy) as f32;
 	let mut step = 0.0;
 
 	loop {
-		if x >= 0 && y >= 0 && x < w && y < h {
+		if x >= 0 && y >= 0 && scissor.contains(x as u32, y as u32) {
 			let t = if length > 0.0 { step / length } else { 0.0 };
 			let z = z0 * (1.0 - t) + z1 * t;
-			let depth_index = (y * w + x) as usize;
 
-			if z < depth_buffer[depth_index] {
-				depth_buffer[depth_index] = z;
+			let offset = (y * w + x) as usize;
 
-				let pixel_index = (depth_index * 4);
-				frame[pixel_index] = 255;
-				frame[pixel_index + 1] = 255;
-				frame[pixel_index + 2] = 255;
-				frame[pixel_index + 3] = 255;
+			let visible = depth_test
+				.is_none_or(|compare| compare.passes(z, buffers.z_buffer[offset]));
+
+			// Lines are overlays, drawn over the encoded frame.
+			if visible {
+				buffers.f_buffer[offset * 4..offset * 4 + 4]
+					.copy_from_slice(&color.to_rgba8());
 			}
 		}
 		if x == x1 && y == y1 {
@@ -75,47 +87,189 @@ pub fn draw_line<T>(
 	}
 }
 
+/// Copies of an instanced model, drawn one after the other so they share
+/// the data cached for their mesh.
+struct Batch<'d> {
+	model: ModelRef<'d>,
+	/// Copies along with their index in the scene.
+	instances: Vec<(usize, InstanceRef)>,
+}
+
 pub struct DrawCall<'d> {
-	objects: Vec<ObjectRef<'d>>,
+	/// Resolved objects along with their index in the scene.
+	objects: Vec<(usize, ObjectRef<'d>)>,
+	/// Instance groups, numbered after the objects in group order.
+	batches: Vec<Batch<'d>>,
 	uniforms: GlobalUniforms,
 }
 
 impl<'d> DrawCall<'d> {
-	pub fn submit_draw_call(scene: &'d Scene, window: WinSize) -> Self {
+	/// Collects the scene objects seen through `view`, rendered into a
+	/// target of size `target`.
+	pub fn submit_draw_call(
+		scene: &'d Scene,
+		view: &View<'_>,
+		target: WinSize,
+	) -> Self {
 		let objects = scene
 			.objects
 			.iter()
 			.map(|obj| obj.resolve(&scene.assets))
+			.enumerate()
 			.collect::<Vec<_>>();
 
-		let aspect = window.aspect();
-		let m_view = scene.camera.get_view_matrix();
-		let m_projection = scene.camera.get_projection_matrix(aspect);
-
-		let uniforms = GlobalUniforms {
-			m_view,
-			m_projection,
-			m_view_projection: m_projection * m_view,
-			screen: ScreenUniforms::from(&window),
-			light: LightUniforms::from(&scene.light),
-			camera: CameraUniforms::from(&scene.camera),
-			lods: LOD::default(),
+		// Instance groups resolve their model once and only compute the
+		// matrices of every copy.
+		let mut index = objects.len();
+
+		let batches = scene
+			.instances
+			.iter()
+			.map(|group| {
+				let (model, instances) = group.resolve(&scene.assets);
+				let instances = instances
+					.map(|instance| {
+						index += 1;
+						(index - 1, instance)
+					})
+					.collect();
+
+				Batch { model, instances }
+			})
+			.collect();
+
+		let uniforms = GlobalUniforms::new(target, view, &scene.light);
+
+		DrawCall {
+			objects,
+			batches,
+			uniforms,
+		}
+	}
+
+	pub fn uniforms(&self) -> &GlobalUniforms {
+		&self.uniforms
+	}
+
+	pub fn set_debug_view(&mut self, view: DebugView) {
+		self.uniforms.debug = view;
+	}
+
+	/// Object whose silhouette is written to the stencil selection bit, its
+	/// own stencil state is ignored.
+	pub fn set_selection(&mut self, object: Option<usize>) {
+		self.uniforms.selection = object;
+	}
+
+	/// Whether any object needs the stencil buffer.
+	pub fn uses_stencil(&self) -> bool {
+		let models = self.objects.iter().map(|(_, object)| &object.model);
+
+		self.uniforms.selection.is_some()
+			|| models
+				.chain(self.batches.iter().map(|batch| &batch.model))
+				.any(|model| model.material.stencil.is_some())
+	}
+
+	/// Marks the depth buffer as filled by a pre-pass, the following
+	/// executions only shade the visible fragments.
+	pub fn set_depth_pre_pass(&mut self, done: bool) {
+		self.uniforms.depth.pre_pass = done;
+	}
+
+	/// Switches to a reverse-Z projection with an infinite far plane.
+	pub fn set_reverse_z(&mut self, camera: &Camera, enabled: bool) {
+		let aspect = self.uniforms.screen.aspect;
+
+		let m_projection = if enabled {
+			camera.get_reverse_z_projection_matrix(aspect)
+		} else {
+			camera.get_projection_matrix(aspect)
 		};
 
-		DrawCall { objects, uniforms }
+		self.uniforms.m_projection = m_projection;
+		self.uniforms.m_view_projection = m_projection * self.uniforms.m_view;
+		self.uniforms.depth.reverse_z = enabled;
 	}
 
-	pub fn execute<F, S>(
-		mut self,
+	/// Sorts the opaque objects, and the copies within opaque instance
+	/// groups, front-to-back by the view space distance of their bounds so
+	/// that occluded fragments fail the depth test early. Objects which
+	/// don't write depth or use the stencil follow in submission order, a
+	/// stencil mask has to be drawn before the geometry it masks.
+	pub fn sort_front_to_back(&mut self) {
+		let m_view = self.uniforms.m_view;
+
+		let distance = |mesh: &Mesh, m_model: &Matrix4| {
+			let bounds = mesh.bounds.transform(m_model);
+			let center = m_view * Vector4::from((bounds.center, 1.0));
+
+			// Distances are never negative, so the bit pattern orders the
+			// same way as the float.
+			center.xyz().magnitude().to_bits()
+		};
+
+		// The sort is stable, the objects of the second group keep their
+		// order.
+		self.objects.sort_by_cached_key(|(_, object)| {
+			if raster::is_opaque(object.model.material) {
+				(false, distance(object.model.mesh, &object.m_model))
+			} else {
+				(true, 0)
+			}
+		});
+
+		for batch in &mut self.batches {
+			if !raster::is_opaque(batch.model.material) {
+				continue;
+			}
+
+			let mesh = batch.model.mesh;
+
+			batch
+				.instances
+				.sort_by_cached_key(|(_, instance)| distance(mesh, &instance.m_model));
+		}
+	}
+
+	/// Hands every object in view to `consumer`, which shades it with the
+	/// shader of its material. The copies of an instance group follow each
+	/// other. Objects are counted here once, whatever the passes drawn for
+	/// them.
+	pub fn execute<F>(
+		&mut self,
 		buffers: &mut Buffers,
-		shader: &S,
+		stats: &mut RenderStats,
 		mut consumer: F,
 	) where
-		F: FnMut(&mut Buffers, ObjectRef<'d>, &mut GlobalUniforms, &S),
-		S: VS + FS,
+		F:
+			FnMut(&mut Buffers, ObjectRef<'d>, &mut GlobalUniforms, &mut RenderStats),
 	{
-		for object in self.objects.into_iter() {
-			consumer(buffers, object, &mut self.uniforms, shader)
+		let frustum = Frustum::from_matrix(&self.uniforms.m_view_projection);
+
+		let objects =
+			self
+				.objects
+				.iter()
+				.copied()
+				.chain(self.batches.iter().flat_map(|batch| {
+					batch.instances.iter().map(|&(index, instance)| {
+						(index, ObjectRef::from_instance(batch.model, instance))
+					})
+				}));
+
+		for (index, object) in objects {
+			stats.objects_submitted += 1;
+
+			let bounds = object.model.mesh.bounds.transform(&object.m_model);
+
+			if !frustum.intersects_sphere(&bounds) {
+				stats.objects_culled += 1;
+				continue;
+			}
+
+			self.uniforms.primitive.object = index;
+			consumer(buffers, object, &mut self.uniforms, stats)
 		}
 	}
 }
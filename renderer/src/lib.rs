@@ -1,10 +1,19 @@
 pub mod animate;
 pub mod buffer;
+pub mod debug;
+pub mod debug_draw;
+pub mod deferred;
 pub mod draw;
 pub mod fps;
+pub mod overlay;
+pub mod picking;
+pub mod post;
 pub mod raster;
 pub mod render;
 pub mod shaders;
+pub mod ssao;
+pub mod stats;
+pub mod view;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,359 @@
+use {
+	crate::{buffer::Buffers, draw, shaders::uniform::GlobalUniforms},
+	pcore::{
+		color::Color,
+		math::{self, Vector3, Vector4},
+	},
+	pscene::material::{DepthCompare, RESERVED_STENCIL_BITS},
+};
+
+/// Stencil bit marking the pixels covered by the selected object, taken
+/// from the bits reserved to the renderer.
+pub const SELECTION_BIT: u8 = RESERVED_STENCIL_BITS;
+
+/// Outline drawn around the selected object.
+#[derive(Clone, Copy)]
+pub struct OutlineSettings {
+	pub color: Color,
+	/// Thickness in pixels.
+	pub width: u32,
+}
+
+impl Default for OutlineSettings {
+	fn default() -> Self {
+		Self {
+			color: Color::from_rgba8([255, 160, 0, 255]),
+			width: 2,
+		}
+	}
+}
+
+/// Parameters of the infinite ground grid.
+#[derive(Clone, Copy)]
+pub struct GridSettings {
+	/// World space distance between two minor lines.
+	pub spacing: f32,
+	/// Every n-th minor line is a major line.
+	pub major_every: u32,
+	/// Height of the grid plane along the y axis.
+	pub height: f32,
+	/// Distance from the camera at which the grid has fully faded out.
+	pub fade_distance: f32,
+	pub minor_color: Color,
+	pub major_color: Color,
+}
+
+impl Default for GridSettings {
+	fn default() -> Self {
+		Self {
+			spacing: 1.0,
+			major_every: 10,
+			height: 0.0,
+			fade_distance: 50.0,
+			minor_color: Color::new_rgb_splat(0.55),
+			major_color: Color::new_rgb_splat(0.8),
+		}
+	}
+}
+
+/// Blends an infinite XZ grid over the frame. Every pixel ray is
+/// intersected with the grid plane and the lines are anti-aliased using
+/// the world space footprint of the pixel. The x and z axes are drawn in
+/// red and blue.
+pub fn grid_pass(
+	buffers: &mut Buffers,
+	uniforms: &GlobalUniforms,
+	settings: &GridSettings,
+) {
+	let width = uniforms.screen.width as usize;
+	let scissor = uniforms.screen.scissor;
+
+	let inv_view_projection = uniforms.m_view_projection.inverse();
+	let eye = uniforms.camera.position;
+
+	let hit = |x: f32, y: f32| {
+		let (ndc_x, ndc_y) = uniforms.screen.to_ndc(x, y);
+
+		let far = inv_view_projection * Vector4::new(ndc_x, ndc_y, 1.0, 1.0);
+		let dir = far.xyz() * (1.0 / far.w) - eye;
+
+		// Rays parallel to or pointing away from the plane never hit it.
+		let t = (settings.height - eye.y) / dir.y;
+		(t > 0.0).then(|| eye + dir * t)
+	};
+
+	let visible = uniforms.depth.resolve(DepthCompare::LessEqual);
+
+	let major = settings.spacing * settings.major_every.max(1) as f32;
+
+	for y in scissor.y as usize..scissor.bottom() as usize {
+		for x in scissor.x as usize..scissor.right() as usize {
+			let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+			let Some(p) = hit(px, py) else {
+				continue;
+			};
+
+			let offset = y * width + x;
+
+			let clip = uniforms.m_view_projection * Vector4::from((p, 1.0));
+			if !visible.passes(clip.z / clip.w, buffers.z_buffer[offset]) {
+				continue;
+			}
+
+			let distance = (p - eye).magnitude();
+			let fade = 1.0 - (distance / settings.fade_distance).clamp(0.0, 1.0);
+
+			if fade <= 0.0 {
+				continue;
+			}
+
+			// Footprint of the pixel on the plane, the neighbours may miss
+			// the plane close to the horizon where the grid has faded.
+			let footprint = match (hit(px + 1.0, py), hit(px, py + 1.0)) {
+				(Some(dx), Some(dy)) => {
+					let dx = dx - p;
+					let dy = dy - p;
+					(dx.x.abs() + dy.x.abs(), dx.z.abs() + dy.z.abs())
+				}
+				_ => continue,
+			};
+
+			let (color, coverage) = if let Some(axis) = axis_line(p, footprint) {
+				axis
+			} else {
+				let major_coverage = line_coverage(p, major, footprint);
+				let minor_coverage = line_coverage(p, settings.spacing, footprint);
+
+				if major_coverage >= minor_coverage {
+					(settings.major_color, major_coverage)
+				} else {
+					(settings.minor_color, minor_coverage)
+				}
+			};
+
+			let alpha = coverage * fade * fade;
+
+			if alpha <= 0.0 {
+				continue;
+			}
+
+			let pixel = &mut buffers.f_buffer[offset * 4..offset * 4 + 4];
+			let background = Color::from_rgba8(pixel.try_into().unwrap());
+
+			pixel.copy_from_slice(&math::lerp(background, color, alpha).to_rgba8());
+		}
+	}
+}
+
+/// Anti-aliased coverage of the lines repeating every `spacing` units.
+#[inline]
+fn line_coverage(p: Vector3, spacing: f32, (fx, fz): (f32, f32)) -> f32 {
+	let distance = |v: f32| {
+		let cell = v / spacing;
+		(cell - cell.round()).abs() * spacing
+	};
+
+	let cx = 1.0 - (distance(p.x) / fx).min(1.0);
+	let cz = 1.0 - (distance(p.z) / fz).min(1.0);
+
+	cx.max(cz)
+}
+
+#[inline]
+fn axis_line(p: Vector3, (fx, fz): (f32, f32)) -> Option<(Color, f32)> {
+	let x_axis = 1.0 - (p.z.abs() / fz).min(1.0);
+	let z_axis = 1.0 - (p.x.abs() / fx).min(1.0);
+
+	if x_axis <= 0.0 && z_axis <= 0.0 {
+		None
+	} else if x_axis >= z_axis {
+		Some((Color::RED, x_axis))
+	} else {
+		Some((Color::BLUE, z_axis))
+	}
+}
+
+/// Draws the world axes as seen from the camera in the bottom left corner
+/// of the viewport, `size` is the length of an axis in pixels.
+pub fn axis_gizmo(buffers: &mut Buffers, uniforms: &GlobalUniforms, size: f32) {
+	let viewport = &uniforms.screen.viewport;
+
+	let margin = size * 1.5;
+	let center = (
+		viewport.x as f32 + margin,
+		viewport.bottom() as f32 - margin,
+	);
+
+	let camera = &uniforms.camera;
+
+	let mut axes = [
+		(Vector3::UNIT_X, Color::RED),
+		(Vector3::UNIT_Y, Color::GREEN),
+		(Vector3::UNIT_Z, Color::BLUE),
+	];
+
+	// Axes pointing away from the viewer are drawn first so the closer
+	// ones stay on top.
+	axes.sort_by(|(a, _), (b, _)| {
+		let a = a.dot(&camera.forward);
+		let b = b.dot(&camera.forward);
+		b.total_cmp(&a)
+	});
+
+	for (axis, color) in axes {
+		let end_x = center.0 + axis.dot(&camera.right) * size;
+		let end_y = center.1 - axis.dot(&camera.up) * size;
+
+		draw::draw_line(
+			buffers,
+			&uniforms.screen,
+			(center.0 as i32, center.1 as i32, 0.0),
+			(end_x as i32, end_y as i32, 0.0),
+			color,
+			None,
+		);
+	}
+}
+
+/// Paints the pixels around the selection mask of the stencil buffer,
+/// every pixel outside the mask within `width` pixels of it is covered.
+/// The disk is dilated in two passes, the distance to the mask along the
+/// rows first then the closest of these distances along the columns.
+pub fn outline_pass(
+	buffers: &mut Buffers,
+	uniforms: &GlobalUniforms,
+	settings: &OutlineSettings,
+) {
+	if buffers.stencil.is_empty() {
+		return;
+	}
+
+	let width = uniforms.screen.width as usize;
+	let scissor = uniforms.screen.scissor;
+
+	let (sx, sy) = (scissor.x as usize, scissor.y as usize);
+	let (sw, sh) = (scissor.width as usize, scissor.height as usize);
+
+	let radius = settings.width as usize;
+	let color = settings.color.to_rgba8();
+
+	// Horizontal distance to the mask, capped past the radius. The mask of
+	// other views sharing the target is ignored.
+	let far = radius + 1;
+	let mut row_distance = vec![far; sw * sh];
+
+	for (y, row) in row_distance.chunks_exact_mut(sw.max(1)).enumerate() {
+		let offset = (sy + y) * width + sx;
+		let stencil = &buffers.stencil[offset..offset + sw];
+
+		let mut distance = far;
+		for (d, &value) in row.iter_mut().zip(stencil) {
+			distance = if value & SELECTION_BIT != 0 {
+				0
+			} else {
+				(distance + 1).min(far)
+			};
+			*d = distance;
+		}
+
+		distance = far;
+		for d in row.iter_mut().rev() {
+			distance = if *d == 0 { 0 } else { (distance + 1).min(far) };
+			*d = (*d).min(distance);
+		}
+	}
+
+	for y in 0..sh {
+		for x in 0..sw {
+			if row_distance[y * sw + x] == 0 {
+				continue;
+			}
+
+			let rows = y.saturating_sub(radius)..(y + radius + 1).min(sh);
+
+			let near = rows.into_iter().any(|ny| {
+				let (dx, dy) = (row_distance[ny * sw + x], ny.abs_diff(y));
+				dx * dx + dy * dy <= radius * radius
+			});
+
+			if near {
+				let offset = (sy + y) * width + sx + x;
+				buffers.f_buffer[offset * 4..offset * 4 + 4].copy_from_slice(&color);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::{OutlineSettings, SELECTION_BIT, outline_pass},
+		crate::{
+			buffer::Buffers,
+			render::WinSize,
+			shaders::uniform::GlobalUniforms,
+			view::{Rect, View},
+		},
+		pcore::math::Vector3,
+		pscene::{camera::Camera, light::Light},
+	};
+
+	const SIZE: u32 = 32;
+
+	#[test]
+	fn outline_covers_the_disk_around_the_mask() {
+		let mut buffers = Buffers::new(SIZE, SIZE);
+		buffers.track_stencil(true);
+
+		let mask = [(4, 4), (5, 4), (20, 9), (12, 26), (30, 30)]
+			.into_iter()
+			.chain((14..18).flat_map(|y| (8..13).map(move |x| (x, y))));
+
+		for (x, y) in mask.clone() {
+			buffers.put_stencil((y * SIZE + x) as usize, SELECTION_BIT);
+		}
+
+		let camera = Camera::new(Vector3::new(0.0, 0.0, 5.0));
+		let scissor = Rect::new(2, 1, 27, 28);
+		let view =
+			View::new(&camera, Rect::new(0, 0, SIZE, SIZE)).with_scissor(scissor);
+		let target = WinSize {
+			width: SIZE,
+			height: SIZE,
+		};
+		let uniforms = GlobalUniforms::new(target, &view, &Light::default());
+
+		let settings = OutlineSettings {
+			width: 3,
+			..Default::default()
+		};
+		let color = settings.color.to_rgba8();
+
+		outline_pass(&mut buffers, &uniforms, &settings);
+
+		let selected = |x: u32, y: u32| {
+			scissor.contains(x, y) && mask.clone().any(|pixel| pixel == (x, y))
+		};
+
+		for y in 0..SIZE {
+			for x in 0..SIZE {
+				let near = (-3..=3i32).any(|dy| {
+					(-3..=3i32).any(|dx| {
+						let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+						dx * dx + dy * dy <= 9
+							&& nx >= 0
+							&& ny >= 0
+							&& selected(nx as u32, ny as u32)
+					})
+				});
+				let expected = scissor.contains(x, y) && !selected(x, y) && near;
+
+				let offset = ((y * SIZE + x) * 4) as usize;
+				let painted = buffers.f_buffer[offset..offset + 4] == color;
+
+				assert_eq!(painted, expected, "pixel ({x}, {y})");
+			}
+		}
+	}
+}
@@ -0,0 +1,53 @@
+use {
+	crate::{buffer::Buffers, shaders::uniform::GlobalUniforms},
+	pcore::math::{Vector3, Vector4},
+};
+
+/// Surface found under a pixel of the last rendered frame.
+#[derive(Debug, Clone, Copy)]
+pub struct Pick {
+	/// Index of the object in `Scene::objects`, instances are numbered
+	/// after the objects in group order.
+	pub object: usize,
+	/// Index of the triangle in the object mesh.
+	pub triangle: usize,
+	/// View space depth, the distance along the camera forward axis.
+	pub depth: f32,
+	/// World space position reconstructed from the depth buffer.
+	pub position: Vector3,
+}
+
+/// Looks up the pixel in the id and depth buffers filled with `uniforms`.
+/// Returns `None` outside the scissor of the view, for background pixels
+/// and when ids are not tracked.
+pub fn pick(
+	buffers: &Buffers,
+	uniforms: &GlobalUniforms,
+	x: u32,
+	y: u32,
+) -> Option<Pick> {
+	if !uniforms.screen.scissor.contains(x, y) {
+		return None;
+	}
+
+	let offset = (y * uniforms.screen.width as u32 + x) as usize;
+
+	let id = (*buffers.ids.get(offset)?)?;
+	let z = buffers.z_buffer[offset];
+
+	let (ndc_x, ndc_y) = uniforms.screen.to_ndc(x as f32 + 0.5, y as f32 + 0.5);
+
+	let world =
+		uniforms.m_view_projection.inverse() * Vector4::new(ndc_x, ndc_y, z, 1.0);
+	let position = world.xyz() * (1.0 / world.w);
+
+	let depth =
+		(position - uniforms.camera.position).dot(&uniforms.camera.forward);
+
+	Some(Pick {
+		object: id.object,
+		triangle: id.triangle,
+		depth,
+		position,
+	})
+}
@@ -0,0 +1,310 @@
+use {
+	crate::{buffer::Buffers, shaders::uniform::GlobalUniforms},
+	pcore::{color::Color, math},
+};
+
+/// Upper bound of the blur chain length.
+pub const MAX_LEVELS: u32 = 8;
+
+/// Parameters of the bloom pass.
+#[derive(Debug, Clone, Copy)]
+pub struct BloomSettings {
+	/// Linear brightness above which the pixels glow.
+	pub threshold: f32,
+	/// Width of the soft transition below the threshold, 0 is a hard cut.
+	pub knee: f32,
+	/// Scale of the glow added back to the frame.
+	pub intensity: f32,
+	/// Halvings of the blur chain, clamped to `MAX_LEVELS`. Every level
+	/// spreads the glow twice as far.
+	pub levels: u32,
+}
+
+impl Default for BloomSettings {
+	fn default() -> Self {
+		Self {
+			threshold: 0.8,
+			knee: 0.4,
+			intensity: 0.6,
+			levels: 5,
+		}
+	}
+}
+
+/// Adds a glow around the bright pixels of the view in the HDR buffer,
+/// run before the encoding. The bright parts are blurred by halving them
+/// down a chain of smaller images and adding the levels back on the way
+/// up.
+pub fn bloom_pass(
+	buffers: &mut Buffers,
+	uniforms: &GlobalUniforms,
+	settings: &BloomSettings,
+) {
+	let scissor = uniforms.screen.scissor;
+	let stride = uniforms.screen.width as u32;
+
+	if scissor.width < 2 || scissor.height < 2 {
+		return;
+	}
+
+	// The glow carries no alpha so adding it keeps the one of the frame.
+	let rgb = Color::new(1.0, 1.0, 1.0, 0.0);
+
+	let (x0, y0) = (scissor.x as usize, scissor.y as usize);
+	let (right, bottom) = (scissor.right() as usize, scissor.bottom() as usize);
+
+	// Bright pass straight into the first half sized level, every texel
+	// averages the kept part of its 2x2 pixels.
+	let mut first = Level::new(
+		(scissor.width as usize).div_ceil(2),
+		(scissor.height as usize).div_ceil(2),
+	);
+
+	for y in 0..first.height {
+		for x in 0..first.width {
+			let mut sum = Color::new(0.0, 0.0, 0.0, 0.0);
+
+			for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+				let px = (x0 + x * 2 + dx).min(right - 1);
+				let py = (y0 + y * 2 + dy).min(bottom - 1);
+
+				let color = buffers.hdr[py * stride as usize + px];
+				sum = sum + color * rgb * bright_weight(color, settings);
+			}
+
+			first.texels[y * first.width + x] = sum * 0.25;
+		}
+	}
+
+	let mut levels = vec![first];
+
+	while levels.len() < settings.levels.clamp(1, MAX_LEVELS) as usize {
+		let last = &levels[levels.len() - 1];
+
+		if last.width < 2 || last.height < 2 {
+			break;
+		}
+
+		levels.push(last.downsample());
+	}
+
+	for i in (1..levels.len()).rev() {
+		let (larger, smaller) = levels.split_at_mut(i);
+		larger[i - 1].add_upsampled(&smaller[0]);
+	}
+
+	// The first level is already smooth, a bilinear stretch is enough to
+	// bring it back to full size.
+	let glow = &levels[0];
+
+	for (y, row) in (y0..bottom).zip(scissor.rows(stride)) {
+		let v = ((y - y0) as f32 + 0.5) / scissor.height as f32;
+
+		for (x, color) in (x0..right).zip(&mut buffers.hdr[row]) {
+			let u = ((x - x0) as f32 + 0.5) / scissor.width as f32;
+
+			*color = *color + glow.sample(u, v) * settings.intensity;
+		}
+	}
+}
+
+/// Share of the color kept by the bright pass, a quadratic curve eases
+/// in over the knee below the threshold.
+#[inline]
+fn bright_weight(color: Color, settings: &BloomSettings) -> f32 {
+	let brightness = color.max_channel();
+	let knee = settings.knee.max(0.0);
+
+	let soft = (brightness - settings.threshold + knee).clamp(0.0, 2.0 * knee);
+	let soft = soft * soft / (4.0 * knee + 1e-4);
+
+	soft.max(brightness - settings.threshold) / brightness.max(1e-4)
+}
+
+/// Image of the blur chain.
+struct Level {
+	width: usize,
+	height: usize,
+	texels: Vec<Color>,
+}
+
+impl Level {
+	fn new(width: usize, height: usize) -> Self {
+		Self {
+			width,
+			height,
+			texels: vec![Color::new(0.0, 0.0, 0.0, 0.0); width * height],
+		}
+	}
+
+	/// Bilinear sample at normalized coordinates, clamped to the edges.
+	#[inline]
+	fn sample(&self, u: f32, v: f32) -> Color {
+		let x = (u * self.width as f32 - 0.5).clamp(0.0, (self.width - 1) as f32);
+		let y = (v * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+
+		let x0 = x as usize;
+		let y0 = y as usize;
+		let x1 = (x0 + 1).min(self.width - 1);
+		let y1 = (y0 + 1).min(self.height - 1);
+
+		let at = |x: usize, y: usize| self.texels[y * self.width + x];
+
+		math::bi_lerp(
+			at(x0, y0),
+			at(x0, y1),
+			at(x1, y0),
+			at(x1, y1),
+			x.fract(),
+			y.fract(),
+		)
+	}
+
+	/// Half sized copy, every texel averages four bilinear taps on the
+	/// corners of its footprint.
+	fn downsample(&self) -> Level {
+		let mut level = Level::new(self.width.div_ceil(2), self.height.div_ceil(2));
+
+		let (du, dv) = (0.5 / self.width as f32, 0.5 / self.height as f32);
+
+		for y in 0..level.height {
+			for x in 0..level.width {
+				let u = (x as f32 + 0.5) / level.width as f32;
+				let v = (y as f32 + 0.5) / level.height as f32;
+
+				let sum = self.sample(u - du, v - dv)
+					+ self.sample(u + du, v - dv)
+					+ self.sample(u - du, v + dv)
+					+ self.sample(u + du, v + dv);
+
+				level.texels[y * level.width + x] = sum * 0.25;
+			}
+		}
+
+		level
+	}
+
+	/// Adds `smaller` stretched over this level through a 3x3 tent filter.
+	fn add_upsampled(&mut self, smaller: &Level) {
+		let (du, dv) = (1.0 / smaller.width as f32, 1.0 / smaller.height as f32);
+
+		for y in 0..self.height {
+			for x in 0..self.width {
+				let u = (x as f32 + 0.5) / self.width as f32;
+				let v = (y as f32 + 0.5) / self.height as f32;
+
+				let tap = |i: f32, j: f32| smaller.sample(u + du * i, v + dv * j);
+
+				let corners =
+					tap(-1.0, -1.0) + tap(1.0, -1.0) + tap(-1.0, 1.0) + tap(1.0, 1.0);
+				let edges =
+					tap(0.0, -1.0) + tap(-1.0, 0.0) + tap(1.0, 0.0) + tap(0.0, 1.0);
+
+				let tent = (corners + edges * 2.0 + tap(0.0, 0.0) * 4.0) * (1.0 / 16.0);
+
+				let texel = &mut self.texels[y * self.width + x];
+				*texel = *texel + tent;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::{BloomSettings, bloom_pass, bright_weight},
+		crate::{
+			buffer::Buffers,
+			render::WinSize,
+			shaders::uniform::GlobalUniforms,
+			view::{Rect, View},
+		},
+		pcore::{color::Color, math::Vector3},
+		pscene::{camera::Camera, light::Light},
+	};
+
+	const SIZE: u32 = 128;
+
+	/// Bloom over a black frame with the given pixels lit.
+	fn bloom_of(lit: &[(usize, usize)]) -> Vec<Color> {
+		let target = WinSize {
+			width: SIZE,
+			height: SIZE,
+		};
+		let camera = Camera::new(Vector3::ZERO);
+		let view = View::new(&camera, Rect::full(target));
+		let uniforms = GlobalUniforms::new(target, &view, &Light::default());
+
+		let mut buffers = Buffers::new(target.width, target.height);
+		buffers.hdr.fill(Color::BLACK);
+
+		for (x, y) in lit {
+			buffers.hdr[y * SIZE as usize + x] = Color::new_rgb_splat(50.0);
+		}
+
+		let settings = BloomSettings {
+			levels: 3,
+			..Default::default()
+		};
+
+		bloom_pass(&mut buffers, &uniforms, &settings);
+
+		buffers.hdr
+	}
+
+	#[test]
+	fn bright_corner_stays_local() {
+		let hdr = bloom_of(&[(0, 0)]);
+
+		// The glow spreads around the corner and fades before the far one.
+		assert!(hdr[1].max_channel() > 0.0);
+		assert_eq!(hdr[hdr.len() - 1].max_channel(), 0.0);
+	}
+
+	#[test]
+	fn glow_is_centered_on_its_source() {
+		// A block matching one texel of the last level, so every level keeps
+		// it centered on pixel 68.
+		let block = (64..72)
+			.flat_map(|y| (64..72).map(move |x| (x, y)))
+			.collect::<Vec<_>>();
+
+		let hdr = bloom_of(&block);
+		let at = |x: usize, y: usize| hdr[y * SIZE as usize + x].max_channel();
+
+		for d in 1..12 {
+			let (before, after) = (at(68 - d, 68), at(67 + d, 68));
+			assert!((before - after).abs() < 1e-4 * before.max(1.0));
+
+			let (above, below) = (at(68, 68 - d), at(68, 67 + d));
+			assert!((above - below).abs() < 1e-4 * above.max(1.0));
+		}
+	}
+
+	#[test]
+	fn bright_pass_eases_in_over_the_knee() {
+		let settings = BloomSettings::default();
+		let weight = |v: f32| bright_weight(Color::new_rgb_splat(v), &settings);
+
+		// Nothing below the knee, a smooth ramp up to the threshold.
+		assert_eq!(weight(0.3), 0.0);
+		assert!(weight(0.5) > 0.0);
+		assert!(weight(0.5) < weight(0.7));
+
+		// Past the knee only the excess over the threshold is kept.
+		let narrow = BloomSettings {
+			threshold: 0.5,
+			knee: 0.1,
+			..settings
+		};
+		let excess = bright_weight(Color::new_rgb_splat(1.0), &narrow);
+		assert!((excess - 0.5).abs() < 1e-4);
+
+		let hard = BloomSettings {
+			knee: 0.0,
+			..settings
+		};
+		assert_eq!(bright_weight(Color::new_rgb_splat(0.79), &hard), 0.0);
+		assert!(bright_weight(Color::new_rgb_splat(0.9), &hard) > 0.0);
+	}
+}
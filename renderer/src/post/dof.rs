@@ -0,0 +1,226 @@
+use {
+	crate::{buffer::Buffers, shaders::uniform::GlobalUniforms},
+	pcore::{
+		color::Color,
+		math::{Vector2, Vector4},
+	},
+	pscene::camera::Camera,
+	std::f32::consts::TAU,
+};
+
+/// Upper bound of the gather kernel size.
+pub const MAX_SAMPLES: u32 = 64;
+
+/// Side in pixels of the tiles bounding the reach of the near field.
+const TILE_SIZE: usize = 16;
+
+/// Parameters of the depth of field pass, the lens itself is described by
+/// the camera.
+#[derive(Debug, Clone, Copy)]
+pub struct DofSettings {
+	/// Largest blur radius in pixels, the circles of confusion are clamped
+	/// to it.
+	pub max_radius: f32,
+	/// Taps of the gather kernel per pixel, clamped to `MAX_SAMPLES`.
+	pub samples: u32,
+}
+
+impl Default for DofSettings {
+	fn default() -> Self {
+		Self {
+			max_radius: 12.0,
+			samples: 24,
+		}
+	}
+}
+
+/// Blurs the view in the HDR buffer by the circle of confusion of the
+/// camera lens at the depth of every pixel. The far field behind the focus
+/// plane only gathers from pixels as far, so sharp objects don't bleed
+/// into it, while the near field spreads over whatever lies behind and is
+/// blended on top.
+pub fn dof_pass(
+	buffers: &mut Buffers,
+	uniforms: &GlobalUniforms,
+	camera: &Camera,
+	settings: &DofSettings,
+) {
+	let width = uniforms.screen.width as usize;
+	let scissor = uniforms.screen.scissor;
+
+	if scissor.is_empty() {
+		return;
+	}
+
+	let inv_projection = uniforms.m_projection.inverse();
+	let clear = buffers.depth_clear();
+
+	let max_radius = settings.max_radius.max(0.0);
+
+	// Signed radius in pixels, negative in the near field.
+	let to_pixels = uniforms.screen.viewport.height as f32 * 0.5;
+
+	let mut coc = vec![0.0; buffers.z_buffer.len()];
+
+	for i in scissor.rows(width as u32).flatten() {
+		let z = buffers.z_buffer[i];
+
+		// Nothing was drawn, the background is at infinity.
+		let depth = if z == clear {
+			f32::INFINITY
+		} else {
+			let (x, y) = (i % width, i / width);
+			let (ndc_x, ndc_y) =
+				uniforms.screen.to_ndc(x as f32 + 0.5, y as f32 + 0.5);
+
+			let v = inv_projection * Vector4::new(ndc_x, ndc_y, z, 1.0);
+			-v.z / v.w
+		};
+
+		coc[i] = (camera.circle_of_confusion(depth) * to_pixels)
+			.clamp(-max_radius, max_radius);
+	}
+
+	let kernel = kernel(settings.samples.clamp(1, MAX_SAMPLES));
+	let near_reach = near_reach(&coc, width, uniforms, max_radius);
+
+	let source = buffers.hdr.clone();
+
+	let at = |x: i32, y: i32| {
+		let x = x.clamp(scissor.x as i32, scissor.right() as i32 - 1) as usize;
+		let y = y.clamp(scissor.y as i32, scissor.bottom() as i32 - 1) as usize;
+
+		y * width + x
+	};
+
+	for y in scissor.y as usize..scissor.bottom() as usize {
+		for x in scissor.x as usize..scissor.right() as usize {
+			let i = y * width + x;
+			let center = coc[i];
+
+			// Far field, only the samples at least as far and blurred enough
+			// to reach the pixel are gathered.
+			let far = if center > 0.5 {
+				let mut sum = source[i];
+				let mut weight = 1.0;
+
+				for k in &kernel {
+					let offset = *k * center;
+					let j = at(
+						x as i32 + offset.x.round() as i32,
+						y as i32 + offset.y.round() as i32,
+					);
+
+					if coc[j] >= offset.magnitude() {
+						sum = sum + source[j];
+						weight += 1.0;
+					}
+				}
+
+				sum * (1.0 / weight)
+			} else {
+				source[i]
+			};
+
+			// Near field, the blurred foreground reaching the pixel from any
+			// direction covers it by the share of the kernel it hits.
+			let reach =
+				near_reach[(y / TILE_SIZE) * tiles_x(uniforms) + x / TILE_SIZE];
+
+			let color = if reach > 0.5 {
+				let mut sum = Color::new(0.0, 0.0, 0.0, 0.0);
+				let mut hits = 0.0;
+
+				for k in &kernel {
+					let offset = *k * reach;
+					let j = at(
+						x as i32 + offset.x.round() as i32,
+						y as i32 + offset.y.round() as i32,
+					);
+
+					if -coc[j] >= offset.magnitude() {
+						sum = sum + source[j];
+						hits += 1.0;
+					}
+				}
+
+				if hits > 0.0 {
+					let near = sum * (1.0 / hits);
+					let alpha = hits / kernel.len() as f32;
+
+					far * (1.0 - alpha) + near * alpha
+				} else {
+					far
+				}
+			} else {
+				far
+			};
+
+			buffers.hdr[i] = color;
+		}
+	}
+}
+
+/// Largest near field radius which may reach the pixels of every tile,
+/// the one of the tile itself grown by its neighbours.
+fn near_reach(
+	coc: &[f32],
+	width: usize,
+	uniforms: &GlobalUniforms,
+	max_radius: f32,
+) -> Vec<f32> {
+	let scissor = uniforms.screen.scissor;
+
+	let tiles_x = tiles_x(uniforms);
+	let tiles_y = (uniforms.screen.height as usize).div_ceil(TILE_SIZE);
+
+	let mut tiles = vec![0.0_f32; tiles_x * tiles_y];
+
+	for y in scissor.y as usize..scissor.bottom() as usize {
+		for x in scissor.x as usize..scissor.right() as usize {
+			let tile = &mut tiles[(y / TILE_SIZE) * tiles_x + x / TILE_SIZE];
+			*tile = tile.max(-coc[y * width + x]);
+		}
+	}
+
+	let spread = (max_radius / TILE_SIZE as f32).ceil() as isize;
+
+	let mut reach = vec![0.0_f32; tiles.len()];
+
+	for ty in 0..tiles_y as isize {
+		for tx in 0..tiles_x as isize {
+			let mut r = 0.0_f32;
+
+			for ny in (ty - spread).max(0)..=(ty + spread).min(tiles_y as isize - 1) {
+				for nx in (tx - spread).max(0)..=(tx + spread).min(tiles_x as isize - 1)
+				{
+					r = r.max(tiles[ny as usize * tiles_x + nx as usize]);
+				}
+			}
+
+			reach[ty as usize * tiles_x + tx as usize] = r;
+		}
+	}
+
+	reach
+}
+
+#[inline]
+fn tiles_x(uniforms: &GlobalUniforms) -> usize {
+	(uniforms.screen.width as usize).div_ceil(TILE_SIZE)
+}
+
+/// Offsets evenly covering the unit disk.
+fn kernel(samples: u32) -> Vec<Vector2> {
+	// Golden angle spiral, like the occlusion kernel.
+	let golden_angle = TAU * (1.0 - 1.0 / 1.618_034);
+
+	(0..samples)
+		.map(|i| {
+			let r = ((i as f32 + 0.5) / samples as f32).sqrt();
+			let (sin, cos) = (i as f32 * golden_angle).sin_cos();
+
+			Vector2::new(r * cos, r * sin)
+		})
+		.collect()
+}
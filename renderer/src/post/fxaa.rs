@@ -0,0 +1,357 @@
+use crate::{buffer::Buffers, shaders::uniform::GlobalUniforms, view::Rect};
+
+/// Speed against quality trade-off of the FXAA pass.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FxaaQuality {
+	/// Only the strong edges, with short searches.
+	Low,
+	#[default]
+	Medium,
+	/// Fainter edges, longer searches and full sub-pixel smoothing.
+	High,
+}
+
+impl FxaaQuality {
+	pub const ALL: [FxaaQuality; 3] =
+		[FxaaQuality::Low, FxaaQuality::Medium, FxaaQuality::High];
+
+	fn preset(self) -> Preset {
+		match self {
+			FxaaQuality::Low => Preset {
+				edge_threshold: 0.25,
+				edge_threshold_min: 0.0833,
+				subpixel: 0.5,
+				steps: &[1.0, 1.5, 2.0, 4.0],
+			},
+			FxaaQuality::Medium => Preset {
+				edge_threshold: 0.166,
+				edge_threshold_min: 0.0625,
+				subpixel: 0.75,
+				steps: &[1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0],
+			},
+			FxaaQuality::High => Preset {
+				edge_threshold: 0.125,
+				edge_threshold_min: 0.0312,
+				subpixel: 1.0,
+				steps: &[1.0, 1.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0],
+			},
+		}
+	}
+}
+
+struct Preset {
+	/// Local contrast relative to the brightest neighbour below which the
+	/// pixel is left alone.
+	edge_threshold: f32,
+	/// Absolute contrast below which the pixel is left alone, skips the
+	/// dark areas.
+	edge_threshold_min: f32,
+	/// Amount of smoothing of the single pixel features.
+	subpixel: f32,
+	/// Distances in pixels walked at each step of the edge search.
+	steps: &'static [f32],
+}
+
+/// Smooths the aliased edges of the view in the encoded frame buffer.
+/// Edges are found from the luminance contrast, followed along to find
+/// their ends, and every pixel is blended with its neighbour across the
+/// edge by its position along it.
+pub fn fxaa_pass(
+	buffers: &mut Buffers,
+	uniforms: &GlobalUniforms,
+	quality: FxaaQuality,
+) {
+	let preset = quality.preset();
+
+	let width = uniforms.screen.width as usize;
+	let scissor = uniforms.screen.scissor;
+
+	if scissor.is_empty() {
+		return;
+	}
+
+	let source = Image::copy(&buffers.f_buffer, width, scissor);
+
+	for y in scissor.y as i32..scissor.bottom() as i32 {
+		for x in scissor.x as i32..scissor.right() as i32 {
+			let Some((dx, dy)) = offset(&source, x, y, &preset) else {
+				continue;
+			};
+
+			let color = source.sample(x as f32 + dx, y as f32 + dy);
+
+			let i = (y as usize * width + x as usize) * 4;
+			buffers.f_buffer[i..i + 3].copy_from_slice(&color);
+		}
+	}
+}
+
+/// Sub-pixel position to resample the pixel at, `None` when it is not on
+/// an edge.
+fn offset(
+	image: &Image,
+	x: i32,
+	y: i32,
+	preset: &Preset,
+) -> Option<(f32, f32)> {
+	let at = |dx: i32, dy: i32| image.luma_at(x + dx, y + dy);
+
+	let m = at(0, 0);
+	let (n, s, w, e) = (at(0, -1), at(0, 1), at(-1, 0), at(1, 0));
+
+	let max = m.max(n).max(s).max(w).max(e);
+	let min = m.min(n).min(s).min(w).min(e);
+	let range = max - min;
+
+	if range < preset.edge_threshold_min.max(max * preset.edge_threshold) {
+		return None;
+	}
+
+	let (nw, ne, sw, se) = (at(-1, -1), at(1, -1), at(-1, 1), at(1, 1));
+
+	// Sub-pixel aliasing, how much the pixel stands out from the average
+	// of its neighbourhood.
+	let average = (2.0 * (n + s + w + e) + nw + ne + sw + se) / 12.0;
+	let subpixel = ((average - m).abs() / range).clamp(0.0, 1.0);
+	let subpixel = smoothstep(subpixel);
+	let subpixel = subpixel * subpixel * preset.subpixel;
+
+	// Orientation of the edge from the second derivatives of the luma.
+	let horizontal_edge = (-2.0 * w + nw + sw).abs()
+		+ 2.0 * (-2.0 * m + n + s).abs()
+		+ (-2.0 * e + ne + se).abs();
+	let vertical_edge = (-2.0 * n + nw + ne).abs()
+		+ 2.0 * (-2.0 * m + w + e).abs()
+		+ (-2.0 * s + sw + se).abs();
+
+	let horizontal = horizontal_edge >= vertical_edge;
+
+	// Neighbours on both sides across the edge, the steepest side is the
+	// one the edge lies on.
+	let (luma_neg, luma_pos) = if horizontal { (n, s) } else { (w, e) };
+	let gradient_neg = luma_neg - m;
+	let gradient_pos = luma_pos - m;
+
+	let neg_steepest = gradient_neg.abs() >= gradient_pos.abs();
+	let gradient = 0.25 * gradient_neg.abs().max(gradient_pos.abs());
+
+	let (across, edge_luma) = if neg_steepest {
+		(-0.5, 0.5 * (luma_neg + m))
+	} else {
+		(0.5, 0.5 * (luma_pos + m))
+	};
+
+	// Walks along the edge from the middle of the edge in both directions
+	// until the luma stops matching the one of the edge.
+	let (ux, uy) = if horizontal { (1.0, 0.0) } else { (0.0, 1.0) };
+	let (ex, ey) = if horizontal {
+		(x as f32, y as f32 + across)
+	} else {
+		(x as f32 + across, y as f32)
+	};
+
+	let walk = |direction: f32| {
+		let mut distance = 0.0;
+		let mut delta = 0.0;
+
+		for step in preset.steps {
+			distance += step;
+
+			let sx = ex + ux * direction * distance;
+			let sy = ey + uy * direction * distance;
+
+			delta = image.luma_sample(sx, sy) - edge_luma;
+
+			if delta.abs() >= gradient {
+				break;
+			}
+		}
+
+		(distance, delta)
+	};
+
+	let (distance_neg, delta_neg) = walk(-1.0);
+	let (distance_pos, delta_pos) = walk(1.0);
+
+	// The closest end of the edge decides, the pixel is blended only when
+	// the luma there varies the same way as at the pixel itself.
+	let (distance, delta) = if distance_neg < distance_pos {
+		(distance_neg, delta_neg)
+	} else {
+		(distance_pos, delta_pos)
+	};
+
+	let m_below = m - edge_luma < 0.0;
+	let edge_offset = if (delta < 0.0) != m_below {
+		0.5 - distance / (distance_neg + distance_pos)
+	} else {
+		0.0
+	};
+
+	let blend = edge_offset.max(subpixel) * across * 2.0;
+
+	Some(if horizontal {
+		(0.0, blend)
+	} else {
+		(blend, 0.0)
+	})
+}
+
+/// Copy of the view in the encoded frame with the luma of every pixel,
+/// addressed in target pixels. Reads out of the view are clamped to its
+/// border.
+struct Image {
+	pixels: Vec<[u8; 3]>,
+	luma: Vec<f32>,
+	width: usize,
+	min: (i32, i32),
+	max: (i32, i32),
+}
+
+impl Image {
+	/// Copies the `rect` of a frame buffer `stride` pixels wide.
+	fn copy(f_buffer: &[u8], stride: usize, rect: Rect) -> Self {
+		let pixels = rect
+			.rows(stride as u32)
+			.flat_map(|row| f_buffer[row.start * 4..row.end * 4].chunks_exact(4))
+			.map(|p| [p[0], p[1], p[2]])
+			.collect::<Vec<_>>();
+
+		Self {
+			luma: pixels.iter().map(|&p| luma(p)).collect(),
+			pixels,
+			width: rect.width as usize,
+			min: (rect.x as i32, rect.y as i32),
+			max: (rect.right() as i32 - 1, rect.bottom() as i32 - 1),
+		}
+	}
+
+	#[inline]
+	fn index(&self, x: i32, y: i32) -> usize {
+		let x = (x.clamp(self.min.0, self.max.0) - self.min.0) as usize;
+		let y = (y.clamp(self.min.1, self.max.1) - self.min.1) as usize;
+
+		y * self.width + x
+	}
+
+	#[inline]
+	fn luma_at(&self, x: i32, y: i32) -> f32 {
+		self.luma[self.index(x, y)]
+	}
+
+	/// Bilinear luma at a pixel position, pixel centers being integers.
+	#[inline]
+	fn luma_sample(&self, x: f32, y: f32) -> f32 {
+		let (x0, y0) = (x.floor(), y.floor());
+		let (tx, ty) = (x - x0, y - y0);
+		let (x0, y0) = (x0 as i32, y0 as i32);
+
+		let top = lerp(self.luma_at(x0, y0), self.luma_at(x0 + 1, y0), tx);
+		let bottom =
+			lerp(self.luma_at(x0, y0 + 1), self.luma_at(x0 + 1, y0 + 1), tx);
+
+		lerp(top, bottom, ty)
+	}
+
+	/// Bilinear color at a pixel position, without alpha.
+	#[inline]
+	fn sample(&self, x: f32, y: f32) -> [u8; 3] {
+		let (x0, y0) = (x.floor(), y.floor());
+		let (tx, ty) = (x - x0, y - y0);
+		let (x0, y0) = (x0 as i32, y0 as i32);
+
+		let texel = |x: i32, y: i32| self.pixels[self.index(x, y)].map(f32::from);
+
+		let (c00, c10) = (texel(x0, y0), texel(x0 + 1, y0));
+		let (c01, c11) = (texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+
+		[0, 1, 2].map(|c| {
+			let top = lerp(c00[c], c10[c], tx);
+			let bottom = lerp(c01[c], c11[c], tx);
+
+			(lerp(top, bottom, ty) + 0.5) as u8
+		})
+	}
+}
+
+/// Perceived brightness of an encoded color, from 0 to 1.
+#[inline(always)]
+fn luma([r, g, b]: [u8; 3]) -> f32 {
+	(0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.0
+}
+
+#[inline(always)]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+	a + (b - a) * t
+}
+
+#[inline(always)]
+fn smoothstep(x: f32) -> f32 {
+	x * x * (3.0 - 2.0 * x)
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::{FxaaQuality, Image, offset},
+		crate::view::Rect,
+	};
+
+	const SIZE: usize = 16;
+
+	/// Frame split between black on the left and white on the right of
+	/// `edge(y)`.
+	fn image(edge: impl Fn(usize) -> usize) -> Image {
+		let f_buffer = (0..SIZE * SIZE)
+			.flat_map(|i| {
+				let value = if i % SIZE < edge(i / SIZE) { 0 } else { 255 };
+				[value, value, value, 255]
+			})
+			.collect::<Vec<_>>();
+
+		Image::copy(&f_buffer, SIZE, Rect::new(0, 0, SIZE as u32, SIZE as u32))
+	}
+
+	#[test]
+	fn flat_areas_are_left_alone() {
+		let image = image(|_| SIZE);
+		let preset = FxaaQuality::High.preset();
+
+		assert!(offset(&image, 8, 8, &preset).is_none());
+	}
+
+	#[test]
+	fn stair_step_is_blended_across_the_edge() {
+		// Vertical edge stepping one pixel right halfway down.
+		let image = image(|y| if y < SIZE / 2 { 7 } else { 8 });
+		let preset = FxaaQuality::Medium.preset();
+
+		// Far from the pixels on both sides of the edge.
+		assert!(offset(&image, 3, 4, &preset).is_none());
+
+		let (dx, dy) = offset(&image, 7, 10, &preset).unwrap();
+		assert!(dx != 0.0 && dx.abs() <= 1.0);
+		assert_eq!(dy, 0.0);
+	}
+
+	#[test]
+	fn copy_is_addressed_in_target_pixels() {
+		// Luma growing with x, copied from a view away from the origin.
+		let f_buffer = (0..SIZE * SIZE)
+			.flat_map(|i| {
+				let value = (i % SIZE * 16) as u8;
+				[value, value, value, 255]
+			})
+			.collect::<Vec<_>>();
+
+		let image = Image::copy(&f_buffer, SIZE, Rect::new(4, 2, 8, 8));
+
+		assert_eq!(image.pixels.len(), 64);
+		assert_eq!(image.luma_at(6, 5), image.luma_at(6, 9));
+		assert_eq!(image.pixels[image.index(6, 5)], [96; 3]);
+
+		// Reads out of the view are clamped to its border.
+		assert_eq!(image.luma_at(0, 0), image.luma_at(4, 2));
+		assert_eq!(image.luma_at(15, 15), image.luma_at(11, 9));
+	}
+}
@@ -0,0 +1,49 @@
+use {
+	crate::{buffer::Buffers, shaders::uniform::GlobalUniforms},
+	pcore::math::Vector3,
+	pscene::texture::ColorLut,
+};
+
+/// Look of the frame, authored in grading tools as a LUT over the encoded
+/// sRGB colors.
+#[derive(Debug, Clone)]
+pub struct ColorGrading {
+	pub lut: ColorLut,
+	/// Blend from the original frame at 0 to the fully graded one at 1.
+	pub strength: f32,
+}
+
+impl ColorGrading {
+	pub fn new(lut: ColorLut) -> Self {
+		Self { lut, strength: 1.0 }
+	}
+}
+
+/// Maps the colors of the view in the encoded frame buffer through the
+/// grading LUT, alpha is kept.
+pub fn grade_pass(
+	buffers: &mut Buffers,
+	uniforms: &GlobalUniforms,
+	grading: &ColorGrading,
+) {
+	let strength = grading.strength.clamp(0.0, 1.0);
+
+	if strength <= 0.0 {
+		return;
+	}
+
+	let rows = uniforms.screen.scissor.rows(uniforms.screen.width as u32);
+
+	for i in rows.flatten() {
+		let pixel = &mut buffers.f_buffer[i * 4..i * 4 + 3];
+
+		let color = Vector3::new(pixel[0] as f32, pixel[1] as f32, pixel[2] as f32)
+			* (1.0 / 255.0);
+		let graded = grading.lut.sample(color);
+		let out = color + (graded - color) * strength;
+
+		for (channel, value) in pixel.iter_mut().zip([out.x, out.y, out.z]) {
+			*channel = (value.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+		}
+	}
+}
@@ -0,0 +1,18 @@
+use crate::{buffer::Buffers, shaders::uniform::GlobalUniforms};
+
+pub mod bloom;
+pub mod dof;
+pub mod fxaa;
+pub mod grade;
+
+/// Encodes the linear colors of the view to sRGB into the frame buffer,
+/// values outside `[0, 1]` are clamped.
+pub fn encode_pass(buffers: &mut Buffers, uniforms: &GlobalUniforms) {
+	let Buffers { f_buffer, hdr, .. } = buffers;
+
+	let rows = uniforms.screen.scissor.rows(uniforms.screen.width as u32);
+
+	for i in rows.flatten() {
+		f_buffer[i * 4..i * 4 + 4].copy_from_slice(&hdr[i].to_srgba8());
+	}
+}
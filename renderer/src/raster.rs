@@ -1,15 +1,25 @@
 use {
 	crate::{
-		buffer::Buffers,
+		buffer::{Buffers, Cursor},
+		debug::{self, DebugView},
+		overlay,
 		shaders::{
-			FS, GVaryings, VS, Varyings, VertexIn, VertexOut, uniform::GlobalUniforms,
+			BlinnPhong, DS, FS, Flat, GVaryings, Matcap, Toon, ToonOutline, VS,
+			Varyings, VertexIn,
+			uniform::{GlobalUniforms, LOD, ScreenUniforms},
 		},
+		stats::{FragmentCounters, RenderStats, Stopwatch},
 	},
 	pcore::{
-		geometry::{IncEdge, bounding_rect, edge_function},
-		math::{Gradient, Vector2, Vector4},
+		geometry::{IncEdge, UV, bounding_rect, edge_function},
+		math::{Gradient, Vector2, Vector3, Vector4},
 	},
-	pscene::{object::ObjectRef, texture},
+	pscene::{
+		material::{CullMode, DepthCompare, FrontFace, MaterialRef, ShaderModel},
+		object::ObjectRef,
+		texture::TextureMap,
+	},
+	std::{any::TypeId, collections::HashMap},
 };
 
 #[derive(Default, Clone, Copy)]
@@ -30,72 +40,418 @@ impl From<(Vector2, f32, f32)> for RasterIn {
 	}
 }
 
-pub fn consume_draw_call<'d, S>(
+/// Vertex after the vertex stage, projection and perspective division,
+/// shared by every triangle referencing it.
+#[derive(Default, Clone, Copy)]
+pub struct PostTransform {
+	pub raster_in: RasterIn,
+	/// The vertex is at or behind the camera plane, triangles using it are
+	/// rejected.
+	pub behind_camera: bool,
+}
+
+impl PostTransform {
+	#[inline]
+	fn new(v_clip: Vector4, screen: &ScreenUniforms) -> Self {
+		if v_clip.w <= 0.0 {
+			return Self {
+				behind_camera: true,
+				..Default::default()
+			};
+		}
+
+		let inv_w = 1.0 / v_clip.w;
+
+		let mut v_ndc = v_clip * inv_w;
+		v_ndc.w = inv_w;
+
+		Self {
+			raster_in: clip_to_screen(&v_ndc, screen),
+			behind_camera: false,
+		}
+	}
+}
+
+/// Mesh drawn with a transform by a vertex stage, the pre-pass and the
+/// color pass of an object share it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct CacheKey {
+	mesh: usize,
+	m_model: [[u32; 4]; 4],
+	stage: TypeId,
+	/// Material inputs of the vertex stage, see `VS::cache_salt`.
+	salt: u32,
+}
+
+impl CacheKey {
+	fn new<S>(shader: &S, object: ObjectRef<'_>) -> Self
+	where
+		S: VS + 'static,
+	{
+		Self {
+			mesh: std::ptr::from_ref(object.model.mesh) as usize,
+			m_model: object.m_model.data.map(|row| row.map(f32::to_bits)),
+			stage: TypeId::of::<S>(),
+			salt: shader.cache_salt(object),
+		}
+	}
+}
+
+/// Post-transform vertex cache of the view being rendered. The face
+/// normals are kept per mesh, shared by every copy of an instanced model,
+/// and the positions per mesh and transform until the view changes. The
+/// varyings are only kept for the object being drawn and only for the
+/// vertices of the triangles which survive culling.
+#[derive(Default)]
+pub struct VertexCache {
+	/// Object space normal of every triangle, not normalized.
+	face_normals: HashMap<usize, Vec<Vector3>>,
+	positions: HashMap<CacheKey, Vec<PostTransform>>,
+	varyings: Vec<Varyings>,
+	shaded: Vec<bool>,
+	/// Allocations of the dropped positions, reused by the next view.
+	spare: Vec<Vec<PostTransform>>,
+}
+
+impl VertexCache {
+	/// Drops the positions, they depend on the view and projection. The
+	/// face normals go too, the meshes may change until the next view.
+	pub fn clear(&mut self) {
+		self.face_normals.clear();
+
+		self
+			.spare
+			.extend(self.positions.drain().map(|(_, mut positions)| {
+				positions.clear();
+				positions
+			}));
+	}
+}
+
+/// A fragment which passed the coverage and depth tests.
+pub struct Fragment {
+	pub offset: usize,
+	pub w: f32,
+	pub varyings: Varyings,
+}
+
+/// Expands `$body` with `$shader` bound to the shader of the shading
+/// model.
+macro_rules! with_shader {
+	($model:expr, $shader:ident => $body:expr) => {
+		match $model {
+			ShaderModel::Flat => {
+				let $shader = &Flat;
+				$body
+			}
+			ShaderModel::BlinnPhong => {
+				let $shader = &BlinnPhong;
+				$body
+			}
+			ShaderModel::Toon => {
+				let $shader = &Toon;
+				$body
+			}
+			ShaderModel::Matcap => {
+				let $shader = &Matcap;
+				$body
+			}
+		}
+	};
+}
+
+/// Shades the object with the shader of its material, followed by its ink
+/// outline when it has one.
+pub fn consume_draw_call<'d>(
+	buffers: &mut Buffers,
+	object: ObjectRef<'d>,
+	uniforms: &mut GlobalUniforms,
+	stats: &mut RenderStats,
+) {
+	with_shader!(object.model.material.shader, shader => {
+		draw_forward(buffers, object, uniforms, stats, shader)
+	});
+
+	if let Some(hull) = outline_hull(object) {
+		draw_forward(buffers, hull, uniforms, stats, &ToonOutline);
+	}
+}
+
+/// Geometry pass of the deferred pipeline, fills the G-buffer instead of
+/// shading the fragments. Only `BlinnPhong` surfaces fit in the G-buffer,
+/// the other shading models and the ink outlines are shaded forward and
+/// miss the occlusion.
+pub fn consume_draw_call_deferred<'d>(
+	buffers: &mut Buffers,
+	object: ObjectRef<'d>,
+	uniforms: &mut GlobalUniforms,
+	stats: &mut RenderStats,
+) {
+	let shader = object.model.material.shader;
+
+	if shader != ShaderModel::BlinnPhong {
+		return consume_draw_call(buffers, object, uniforms, stats);
+	}
+
+	process_triangles(
+		buffers,
+		object,
+		uniforms,
+		stats,
+		&BlinnPhong,
+		true,
+		|buffers, uniforms, varyings, raster_in| {
+			rasterize_surface(
+				buffers,
+				object,
+				uniforms,
+				varyings,
+				raster_in,
+				&BlinnPhong,
+			)
+		},
+	);
+
+	if let Some(hull) = outline_hull(object) {
+		draw_forward(buffers, hull, uniforms, stats, &ToonOutline);
+	}
+}
+
+/// Depth-only pass, fills the depth buffer from the vertex positions alone
+/// so the following color pass with an equal depth test shades every pixel
+/// at most once. Objects which don't write depth or are masked by the
+/// stencil are skipped and keep their own depth test.
+pub fn consume_draw_call_depth<'d>(
+	buffers: &mut Buffers,
+	object: ObjectRef<'d>,
+	uniforms: &mut GlobalUniforms,
+	stats: &mut RenderStats,
+) {
+	if !is_opaque(object.model.material) {
+		return;
+	}
+
+	with_shader!(object.model.material.shader, shader => {
+		draw_depth(buffers, object, uniforms, stats, shader)
+	});
+
+	// The outline passes the equal depth test of the color pass only if
+	// it is part of the pre-pass too.
+	if let Some(hull) = outline_hull(object) {
+		draw_depth(buffers, hull, uniforms, stats, &ToonOutline);
+	}
+}
+
+fn draw_forward<'d, S>(
 	buffers: &mut Buffers,
 	object: ObjectRef<'d>,
 	uniforms: &mut GlobalUniforms,
+	stats: &mut RenderStats,
 	shader: &S,
 ) where
-	S: VS + FS,
+	S: VS + FS + 'static,
 {
-	let w = uniforms.screen.width as i32;
-	let h = uniforms.screen.height as i32;
+	process_triangles(
+		buffers,
+		object,
+		uniforms,
+		stats,
+		shader,
+		true,
+		|buffers, uniforms, varyings, raster_in| {
+			rasterize(buffers, object, uniforms, varyings, raster_in, shader)
+		},
+	);
+}
+
+fn draw_depth<'d, S>(
+	buffers: &mut Buffers,
+	object: ObjectRef<'d>,
+	uniforms: &mut GlobalUniforms,
+	stats: &mut RenderStats,
+	shader: &S,
+) where
+	S: VS + 'static,
+{
+	process_triangles(
+		buffers,
+		object,
+		uniforms,
+		stats,
+		shader,
+		false,
+		|buffers, uniforms, _, raster_in| {
+			rasterize_depth(buffers, object, uniforms, raster_in)
+		},
+	);
+}
 
-	for v in object.model.mesh.iter_triangles() {
-		let [v0, v1, v2] = v;
+/// The object drawn with its front faces culled, the inverted hull of the
+/// ink outline of toon materials.
+#[inline]
+fn outline_hull(object: ObjectRef<'_>) -> Option<ObjectRef<'_>> {
+	let material = object.model.material;
 
-		let face_normal = (v1.position - v0.position)
-			.cross(&(v2.position - v0.position))
-			.normalize();
+	if material.shader != ShaderModel::Toon || material.toon.outline.is_none() {
+		return None;
+	}
 
-		let mut v_out = [VertexOut::default(); 3];
+	let mut hull = object;
+	hull.model.material.cull_mode = CullMode::Front;
 
-		for i in 0..3 {
-			let v_in = VertexIn {
-				attributes: v[i],
-				face_normal,
-			};
+	Some(hull)
+}
 
-			v_out[i] = shader.shade_vertex(v_in, object, uniforms);
-		}
+/// Runs the vertex stage once per unique vertex of the object, unless an
+/// earlier pass of the view already did, then assembles the triangles from
+/// the transformed vertices, rejects the ones behind the camera or culled
+/// and hands the survivors to `raster`. The varyings are only resolved for
+/// the survivors and when `varyings_needed` is set.
+fn process_triangles<'d, S, R>(
+	buffers: &mut Buffers,
+	object: ObjectRef<'d>,
+	uniforms: &mut GlobalUniforms,
+	stats: &mut RenderStats,
+	shader: &S,
+	varyings_needed: bool,
+	mut raster: R,
+) where
+	S: VS + 'static,
+	R: FnMut(
+		&mut Buffers,
+		&mut GlobalUniforms,
+		[Varyings; 3],
+		[RasterIn; 3],
+	) -> FragmentCounters,
+{
+	let mut stopwatch = Stopwatch::start();
+
+	let mesh = object.model.mesh;
+	let vertices = mesh.vertex_buffer();
+
+	// Post-transform cache, every unique vertex goes through the vertex
+	// stage once and the triangles only reference the results.
+	let mut cache = std::mem::take(&mut buffers.vertex_cache);
+
+	cache.shaded.clear();
+	cache.shaded.resize(vertices.len(), false);
+	cache.varyings.resize(vertices.len(), Varyings::default());
+
+	let key = CacheKey::new(shader, object);
+
+	let face_normals = cache.face_normals.entry(key.mesh).or_insert_with(|| {
+		mesh
+			.index_buffer()
+			.chunks_exact(3)
+			.map(|ids| {
+				let [p0, p1, p2] =
+					[0, 1, 2].map(|i| vertices[ids[i] as usize].position);
+				(p1 - p0).cross(&(p2 - p0))
+			})
+			.collect()
+	});
+
+	if !cache.positions.contains_key(&key) {
+		let mut positions = cache.spare.pop().unwrap_or_default();
+
+		positions.extend(vertices.iter().enumerate().map(|(i, &attributes)| {
+			let input = VertexIn { attributes };
+
+			// Depth-only passes skip the varyings altogether.
+			if !varyings_needed {
+				let clip = shader.shade_position(input, object, uniforms);
+				return PostTransform::new(clip, &uniforms.screen);
+			}
 
-		let outside_clip_space = v_out.iter().any(|out| out.clip.w <= 0.0);
+			let v_out = shader.shade_vertex(input, object, uniforms);
+			let position = PostTransform::new(v_out.clip, &uniforms.screen);
 
-		if outside_clip_space {
-			continue;
-		}
+			if !position.behind_camera {
+				cache.varyings[i] =
+					shader.perspective_divide(v_out.vary, &position.raster_in);
+				cache.shaded[i] = true;
+			}
+
+			position
+		}));
+
+		stats.vertices_shaded += vertices.len() as u32;
+		cache.positions.insert(key, positions);
+	}
 
-		let mut r_vertices = [RasterIn::default(); 3];
-		let mut varyings = [Varyings::default(); 3];
+	let positions = &cache.positions[&key];
 
-		// This block is applying:
-		//
-		// - Perspective division to clip space vertex
-		// - Clip space to screen space transformation
-		for i in 0..3 {
-			let v_clip = v_out[i].clip;
-			let inv_w = 1.0 / v_clip.w;
+	let material = object.model.material;
 
-			let mut v_ndc = v_clip * inv_w;
-			v_ndc.w = inv_w;
+	for (triangle, ids) in mesh.index_buffer().chunks_exact(3).enumerate() {
+		let [i0, i1, i2] = [0, 1, 2].map(|i| ids[i] as usize);
+		let [v0, v1, v2] = [i0, i1, i2].map(|i| positions[i]);
 
-			r_vertices[i] = clip_to_screen(&v_ndc, w as f32, h as f32);
-			varyings[i] = v_out[i].vary;
+		stats.triangles_submitted += 1;
+
+		if v0.behind_camera || v1.behind_camera || v2.behind_camera {
+			stats.triangles_clipped += 1;
+			continue;
 		}
 
-		// Backface culling
-		if is_backfacing(r_vertices[0].s, r_vertices[1].s, r_vertices[2].s) {
+		let r_vertices = [v0.raster_in, v1.raster_in, v2.raster_in];
+
+		// Face culling
+		let back_facing = is_backfacing(
+			r_vertices[0].s,
+			r_vertices[1].s,
+			r_vertices[2].s,
+			material.front_face,
+		);
+
+		let culled = match material.cull_mode {
+			CullMode::Back => back_facing,
+			CullMode::Front => !back_facing,
+			CullMode::None => false,
+		};
+
+		if culled {
+			stats.triangles_backfacing += 1;
 			continue;
 		}
 
-		// Perspective division:
-		// uv, normal, tangents and varyings
-		for i in 0..3 {
-			varyings[i] = shader.perspective_divide(varyings[i], &r_vertices[i]);
+		uniforms.face_normal = (object.m_normal
+			* Vector4::from((face_normals[triangle], 0.0)))
+		.xyz()
+		.normalize();
+		uniforms.primitive.triangle = triangle;
+		uniforms.primitive.back_facing = back_facing;
+		stats.triangles_rasterized += 1;
+		stats.timings.vertex += stopwatch.lap();
+
+		// Vertices transformed by an earlier pass still need their varyings.
+		if varyings_needed {
+			for (i, position) in [(i0, v0), (i1, v1), (i2, v2)] {
+				if cache.shaded[i] {
+					continue;
+				}
+
+				let attributes = vertices[i];
+				let v_out =
+					shader.shade_vertex(VertexIn { attributes }, object, uniforms);
+
+				cache.varyings[i] =
+					shader.perspective_divide(v_out.vary, &position.raster_in);
+				cache.shaded[i] = true;
+				stats.vertices_shaded += 1;
+			}
 		}
 
-		rasterize(buffers, object, uniforms, varyings, r_vertices, shader);
+		let mut varyings = [i0, i1, i2].map(|i| cache.varyings[i]);
+		shader.assemble_triangle(&mut varyings, uniforms.face_normal);
+
+		*stats += raster(buffers, uniforms, varyings, r_vertices);
+		stats.timings.raster += stopwatch.lap();
 	}
+
+	buffers.vertex_cache = cache;
+
+	stats.timings.vertex += stopwatch.lap();
 }
 
 pub fn rasterize<'d, S>(
@@ -105,11 +461,169 @@ pub fn rasterize<'d, S>(
 	varyings: [Varyings; 3],
 	raster_in: [RasterIn; 3],
 	shader: &S,
-) where
+) -> FragmentCounters
+where
 	S: FS,
 {
+	scan_triangle(
+		buffers,
+		object,
+		uniforms,
+		varyings,
+		raster_in,
+		shader,
+		|cursor, buffers, uniforms, fragment| {
+			let color = match uniforms.debug {
+				DebugView::Off => {
+					shader.shade_pixel(fragment.varyings, object, uniforms)
+				}
+				view => {
+					debug::shade_fragment(view, &fragment.varyings, fragment.w, uniforms)
+				}
+			};
+
+			cursor.put_pixel(color);
+
+			// Forward shaded fragments of the deferred pipeline hide the
+			// surface lit afterwards.
+			if let Some(surface) = buffers.g_buffer.get_mut(fragment.offset) {
+				*surface = None;
+			}
+		},
+	)
+}
+
+/// Rasterizes the triangle into the G-buffer, lighting is deferred to
+/// `deferred::lighting_pass`.
+pub fn rasterize_surface<'d, S>(
+	buffers: &mut Buffers,
+	object: ObjectRef<'d>,
+	uniforms: &mut GlobalUniforms,
+	varyings: [Varyings; 3],
+	raster_in: [RasterIn; 3],
+	shader: &S,
+) -> FragmentCounters
+where
+	S: FS + DS,
+{
+	scan_triangle(
+		buffers,
+		object,
+		uniforms,
+		varyings,
+		raster_in,
+		shader,
+		|_, buffers, uniforms, fragment| {
+			let surface = shader.shade_surface(fragment.varyings, object, uniforms);
+			buffers.g_buffer[fragment.offset] = Some(surface);
+		},
+	)
+}
+
+/// Rasterizes only the depth of the triangle.
+pub fn rasterize_depth(
+	buffers: &mut Buffers,
+	object: ObjectRef<'_>,
+	uniforms: &GlobalUniforms,
+	raster_in: [RasterIn; 3],
+) -> FragmentCounters {
+	let mut counters = FragmentCounters::default();
+
+	let compare = uniforms.depth.resolve(object.model.material.depth_compare);
+
+	let w = uniforms.screen.width as i32;
+
+	let [s0, s1, s2] = raster_in.map(|r| r.s);
+
+	let area = edge_function(s0, s1, s2);
+	let inv_area = 1.0 / area;
+
+	let (min, max) = bounding_rect(s0, s1, s2);
+
+	let (min_x, min_y, max_x, max_y) = scissor_bounds(min, max, &uniforms.screen);
+
+	if min_x > max_x || min_y > max_y {
+		return counters;
+	}
+
+	let screen = [s0, s1, s2];
+
+	let dx = (min_x as f32 + 0.5) - s0.x;
+	let dy = (min_y as f32 + 0.5) - s0.y;
+
+	let g_z = Gradient::new(raster_in.map(|r| r.z), screen, inv_area);
+	let mut init_z = g_z.sample_at(dx, dy);
+
+	let inc_edge = IncEdge::new(s0, s1, s2, Some(inv_area));
+	let mut init_w = inc_edge.weights(min_x as f32 + 0.5, min_y as f32 + 0.5);
+
+	for y in min_y..=max_y {
+		let (mut w0, mut w1, mut w2) = init_w;
+		let mut c_z = init_z;
+
+		let row_offset = (y * w + min_x) as usize;
+		let mut buf_cursor = buffers.get_cursor(row_offset);
+
+		for _ in min_x..=max_x {
+			let is_outside = w0 < 0.0 || w1 < 0.0 || w2 < 0.0;
+			let depth_pass =
+				!is_outside && compare.passes(c_z, buf_cursor.get_depth());
+
+			counters.tested += !is_outside as u64;
+			counters.depth_rejected += (!is_outside && !depth_pass) as u64;
+
+			if depth_pass {
+				buf_cursor.put_depth(c_z);
+			}
+
+			(w0, w1, w2) = inc_edge.step_x(w0, w1, w2);
+			g_z.step_x(&mut c_z);
+
+			buf_cursor.step();
+		}
+
+		init_w = inc_edge.step_y(init_w.0, init_w.1, init_w.2);
+		g_z.step_y(&mut init_z);
+	}
+
+	counters
+}
+
+/// Walks the pixels of the triangle bounds, runs the depth test and
+/// recovers the perspective correct varyings of every visible fragment
+/// before handing it to `emit`.
+fn scan_triangle<'d, S, F>(
+	buffers: &mut Buffers,
+	object: ObjectRef<'d>,
+	uniforms: &mut GlobalUniforms,
+	varyings: [Varyings; 3],
+	raster_in: [RasterIn; 3],
+	shader: &S,
+	mut emit: F,
+) -> FragmentCounters
+where
+	S: FS,
+	F: FnMut(&Cursor, &mut Buffers, &GlobalUniforms, Fragment),
+{
+	let mut counters = FragmentCounters::default();
+
+	let material = object.model.material;
+
+	let stencil = material.stencil;
+
+	// The selected object marks its whole silhouette for the outline pass,
+	// including the parts hidden behind other objects.
+	let selected = uniforms.selection == Some(uniforms.primitive.object);
+
+	// After a pre-pass the depth buffer already holds the visible surface
+	// of every object writing depth.
+	let compare = if uniforms.depth.pre_pass && is_opaque(object.model.material) {
+		DepthCompare::Equal
+	} else {
+		uniforms.depth.resolve(material.depth_compare)
+	};
+
 	let w = uniforms.screen.width as i32;
-	let h = uniforms.screen.height as i32;
 
 	let [
 		RasterIn {
@@ -134,13 +648,10 @@ pub fn rasterize<'d, S>(
 
 	let (min, max) = bounding_rect(s0, s1, s2);
 
-	let min_x = min.x.max(0.0) as i32;
-	let min_y = min.y.max(0.0) as i32;
-	let max_x = max.x.min((w - 1) as f32) as i32;
-	let max_y = max.y.min((h - 1) as f32) as i32;
+	let (min_x, min_y, max_x, max_y) = scissor_bounds(min, max, &uniforms.screen);
 
 	if min_x > max_x || min_y > max_y {
-		return;
+		return counters;
 	}
 
 	let screen = [s0, s1, s2];
@@ -169,23 +680,66 @@ pub fn rasterize<'d, S>(
 		let mut c_inv_w = init_inv_w;
 		let mut c_z = init_z;
 
-		let offset = (y * w + min_x) as usize;
-		let mut buf_cursor = buffers.get_cursor(offset);
+		let row_offset = (y * w + min_x) as usize;
+		let mut buf_cursor = buffers.get_cursor(row_offset);
 
-		for _ in min_x..=max_x {
-			let is_outside = w0 < 0.0 || w1 < 0.0 || w2 < 0.0;
+		for offset in row_offset..=row_offset + (max_x - min_x) as usize {
+			let covered = !(w0 < 0.0 || w1 < 0.0 || w2 < 0.0);
 
-			if !is_outside && c_z < buf_cursor.get_depth() {
+			let stencil_pass = match &stencil {
+				Some(state) if covered => state.test(buffers.stencil(offset)),
+				_ => true,
+			};
+
+			let depth_pass =
+				covered && stencil_pass && compare.passes(c_z, buf_cursor.get_depth());
+
+			counters.tested += covered as u64;
+			counters.stencil_rejected += (covered && !stencil_pass) as u64;
+			counters.depth_rejected +=
+				(covered && stencil_pass && !depth_pass) as u64;
+
+			if let Some(state) = &stencil
+				&& covered
+			{
+				let op = match (stencil_pass, depth_pass) {
+					(false, _) => state.fail,
+					(true, false) => state.depth_fail,
+					(true, true) => state.pass,
+				};
+
+				let value = buffers.stencil(offset);
+				buffers.put_stencil(offset, state.apply(op, value));
+			}
+
+			if selected && covered {
+				let value = buffers.stencil(offset);
+				buffers.put_stencil(offset, value | overlay::SELECTION_BIT);
+			}
+
+			if depth_pass {
 				let w_lerped = 1.0 / c_inv_w;
 
 				let varyings = shader.recover_value(&c_varyings, w_lerped);
 
 				lods(object, &g_varyings, &varyings, &g_inv_w, w_lerped, uniforms);
+				uniforms.occlusion = buffers.occlusion(offset);
 
-				let color = shader.shade_pixel(varyings, object, uniforms);
+				if material.depth_write {
+					buf_cursor.put_depth(c_z);
+				}
 
-				buf_cursor.put_depth(c_z);
-				buf_cursor.put_pixel(color);
+				buffers.put_id(offset, uniforms.primitive);
+
+				let fragment = Fragment {
+					offset,
+					w: w_lerped,
+					varyings,
+				};
+
+				emit(&buf_cursor, buffers, uniforms, fragment);
+				buffers.count_fragment(offset);
+				counters.shaded += 1;
 			}
 
 			(w0, w1, w2) = inc_edge.step_x(w0, w1, w2);
@@ -202,6 +756,15 @@ pub fn rasterize<'d, S>(
 		g_inv_w.step_y(&mut init_inv_w);
 		g_z.step_y(&mut init_z);
 	}
+
+	counters
+}
+
+/// Whether the material is drawn in any order, it writes depth and is not
+/// masked by the stencil. Only these take part in the depth pre-pass.
+#[inline]
+pub(crate) fn is_opaque(material: MaterialRef<'_>) -> bool {
+	material.depth_write && material.stencil.is_none()
 }
 
 fn lods<'d>(
@@ -212,12 +775,7 @@ fn lods<'d>(
 	w: f32,
 	uniforms: &mut GlobalUniforms,
 ) {
-	let _material = object.model.material;
-
-	// // Inv depth
-	// let inv_w = inv_w;
-	// // Recovered depth
-	// let w = 1.0 / inv_w;
+	let material = object.model.material;
 
 	let inv_w_dx = g_inv_w.da_dx;
 	let inv_w_dy = g_inv_w.da_dy;
@@ -235,32 +793,60 @@ fn lods<'d>(
 	let duv_dx = (uv_over_w_dx - uv * inv_w_dx) * w;
 	let duv_dy = (uv_over_w_dy - uv * inv_w_dy) * w;
 
-	// Note for now we are using the same lod due to same texture sizes
-	// later on we need to optimize to sort at handle cases for similar
-	// sizes.
-	let lod = texture::sized_lod(1024.0, 1024.0, duv_dx, duv_dy);
-
-	// if let Some(_) = material.albedo {
-	// 	let lod = albedo.compute_lod(duv_dx, duv_dy);
-	// 	uniforms.lods.albedo = Some(lod)
-	// }
-
-	// if let Some(_) = material.normal {
-	// 	let lod = n_map.compute_lod(duv_dx, duv_dy);
-	// 	uniforms.lods.normal = Some(lod)
-	// }
+	// Every map has its own footprint, the maps may differ in size.
+	uniforms.lods = LOD {
+		albedo: map_lod(material.albedo, duv_dx, duv_dy),
+		normal: map_lod(material.normal, duv_dx, duv_dy),
+		height: map_lod(material.height, duv_dx, duv_dy),
+		emissive: map_lod(material.emissive_map, duv_dx, duv_dy),
+		specular: map_lod(material.specular_map, duv_dx, duv_dy),
+		occlusion: map_lod(material.occlusion_map, duv_dx, duv_dy),
+	};
+}
 
-	uniforms.lods.albedo = Some(lod);
-	uniforms.lods.normal = Some(lod);
+#[inline(always)]
+fn map_lod<T>(
+	map: Option<&TextureMap<T>>,
+	duv_dx: UV,
+	duv_dy: UV,
+) -> Option<f32> {
+	map.map(|map| map.lod(duv_dx, duv_dy))
 }
 
-pub fn clip_to_screen(v_ndc: &Vector4, width: f32, height: f32) -> RasterIn {
-	let screen_x = (v_ndc.x + 1.0) * 0.5 * width;
-	let screen_y = (1.0 - (v_ndc.y + 1.0) * 0.5) * height;
+pub fn clip_to_screen(v_ndc: &Vector4, screen: &ScreenUniforms) -> RasterIn {
+	let (screen_x, screen_y) = screen.to_screen(v_ndc.x, v_ndc.y);
 
 	(Vector2::new(screen_x, screen_y), v_ndc.z, v_ndc.w).into()
 }
 
-pub fn is_backfacing(v0: Vector2, v1: Vector2, v2: Vector2) -> bool {
-	edge_function(v0, v1, v2) < 0.0
+/// Pixel bounds of the triangle rectangle clamped to the scissor, empty
+/// when `min > max` on either axis.
+#[inline]
+fn scissor_bounds(
+	min: Vector2,
+	max: Vector2,
+	screen: &ScreenUniforms,
+) -> (i32, i32, i32, i32) {
+	let scissor = &screen.scissor;
+
+	let min_x = min.x.max(scissor.x as f32) as i32;
+	let min_y = min.y.max(scissor.y as f32) as i32;
+	let max_x = max.x.min(scissor.right() as f32 - 1.0) as i32;
+	let max_y = max.y.min(scissor.bottom() as f32 - 1.0) as i32;
+
+	(min_x, min_y, max_x, max_y)
+}
+
+pub fn is_backfacing(
+	v0: Vector2,
+	v1: Vector2,
+	v2: Vector2,
+	front_face: FrontFace,
+) -> bool {
+	let area = edge_function(v0, v1, v2);
+
+	match front_face {
+		FrontFace::CounterClockwise => area < 0.0,
+		FrontFace::Clockwise => area > 0.0,
+	}
 }
@@ -1,7 +1,27 @@
 use {
-	crate::{buffer::Buffers, draw::DrawCall, raster, shaders::BlinnPhong},
-	pcore::error::PResult,
-	pscene::global::Scene,
+	crate::{
+		buffer::Buffers,
+		debug::{self, DebugView},
+		debug_draw::DebugDraw,
+		deferred::{self, Pipeline},
+		draw::DrawCall,
+		overlay::{self, GridSettings, OutlineSettings},
+		picking::{self, Pick},
+		post::{
+			self, bloom::BloomSettings, dof::DofSettings, fxaa::FxaaQuality,
+			grade::ColorGrading,
+		},
+		raster,
+		shaders::{BlinnPhong, uniform::GlobalUniforms},
+		ssao::{self, SsaoSettings},
+		stats::{RenderStats, Stopwatch},
+		view::{Rect, View},
+	},
+	pcore::{
+		color::Color,
+		error::{PError, PResult},
+	},
+	pscene::{assets::registry::AlbedoHandle, camera::Camera, global::Scene},
 };
 
 #[derive(Clone, Copy)]
@@ -16,9 +36,33 @@ impl WinSize {
 	}
 }
 
+/// Length in pixels of the corner axis gizmo arms.
+const AXIS_GIZMO_SIZE: f32 = 30.0;
+
 pub struct Renderer {
 	win_size: WinSize,
 	buffers: Buffers,
+	/// Buffers of the render-to-texture passes, sized to the texture.
+	offscreen: Buffers,
+	debug_view: DebugView,
+	pipeline: Pipeline,
+	depth_pre_pass: bool,
+	ssao: Option<SsaoSettings>,
+	bloom: Option<BloomSettings>,
+	dof: Option<DofSettings>,
+	fxaa: Option<FxaaQuality>,
+	grading: Option<ColorGrading>,
+	debug_draw: DebugDraw,
+	grid: Option<GridSettings>,
+	axis_gizmo: bool,
+	picking: bool,
+	reverse_z: bool,
+	selection: Option<usize>,
+	outline: OutlineSettings,
+	/// Uniforms of every view of the last rendered frame, used to resolve
+	/// picks.
+	frame_uniforms: Vec<GlobalUniforms>,
+	stats: RenderStats,
 }
 
 impl Renderer {
@@ -29,28 +73,299 @@ impl Renderer {
 				height: win_height,
 			},
 			buffers: Buffers::new(win_width, win_height),
+			offscreen: Buffers::default(),
+			debug_view: DebugView::Off,
+			pipeline: Pipeline::Forward,
+			depth_pre_pass: false,
+			ssao: None,
+			bloom: None,
+			dof: None,
+			fxaa: None,
+			grading: None,
+			debug_draw: DebugDraw::default(),
+			grid: None,
+			axis_gizmo: false,
+			picking: false,
+			reverse_z: false,
+			selection: None,
+			outline: OutlineSettings::default(),
+			frame_uniforms: Vec::new(),
+			stats: RenderStats::default(),
 		}
 	}
 
+	/// Renders the scene from its camera over the whole target.
 	pub fn render<R>(&mut self, scene: &mut Scene, target: &mut R) -> PResult<()>
 	where
 		R: AsMut<[u8]> + ?Sized,
 	{
-		self.reset_buffers();
+		let view = View::new(&scene.camera, Rect::full(self.win_size));
+		self.render_views(scene, &[view], target)
+	}
+
+	/// Renders the scene once per view into the same target, the views
+	/// are drawn in order so later ones cover the earlier ones where they
+	/// overlap.
+	pub fn render_views<R>(
+		&mut self,
+		scene: &Scene,
+		views: &[View<'_>],
+		target: &mut R,
+	) -> PResult<()>
+	where
+		R: AsMut<[u8]> + ?Sized,
+	{
+		let deferred = self.begin_frame();
+
+		for (i, view) in views.iter().enumerate() {
+			self.render_view(scene, view, deferred, i > 0, true);
+		}
 
-		let draw_call = DrawCall::submit_draw_call(scene, self.win_size);
-		let blinn_phong = BlinnPhong;
+		self.debug_draw.clear();
 
-		draw_call.execute(
-			&mut self.buffers,
-			&blinn_phong,
-			raster::consume_draw_call,
-		);
+		let mut stopwatch = Stopwatch::start();
 
 		target.as_mut().copy_from_slice(&self.buffers.f_buffer);
+
+		self.stats.timings.copy = stopwatch.lap();
+		Ok(())
+	}
+
+	/// Renders the scene from `camera` into the albedo map of the registry
+	/// at the size of the texture, then rebuilds its mip chain. Materials
+	/// bound to the texture sample the new content when the frame is
+	/// rendered afterwards. Overlays and debug lines are left out and the
+	/// stats and picks of the main frame are kept.
+	pub fn render_to_texture(
+		&mut self,
+		scene: &mut Scene,
+		camera: &Camera,
+		texture: &AlbedoHandle,
+	) -> PResult<()> {
+		let map = scene
+			.assets
+			.get_albedo(texture)
+			.ok_or(PError::MissingAsset("albedo"))?;
+
+		let (width, height) = map.dimensions();
+		let size = WinSize {
+			width: width as u32,
+			height: height as u32,
+		};
+
+		// The offscreen pass works on its own buffers and keeps the state
+		// of the main frame aside.
+		let win_size = std::mem::replace(&mut self.win_size, size);
+		let stats = std::mem::take(&mut self.stats);
+		let frame_uniforms = std::mem::take(&mut self.frame_uniforms);
+
+		self.offscreen.resize(size.width, size.height);
+		std::mem::swap(&mut self.buffers, &mut self.offscreen);
+
+		let deferred = self.begin_frame();
+		let view = View::new(camera, Rect::full(size));
+		self.render_view(scene, &view, deferred, false, false);
+
+		std::mem::swap(&mut self.buffers, &mut self.offscreen);
+		self.win_size = win_size;
+		self.stats = stats;
+		self.frame_uniforms = frame_uniforms;
+
+		let texels = self
+			.offscreen
+			.f_buffer
+			.chunks_exact(4)
+			.map(|pixel| Color::from_srgba8(pixel.try_into().unwrap()))
+			.collect();
+
+		if let Some(map) = scene.assets.get_albedo_mut(texture) {
+			map.update(width, height, texels);
+		}
+
 		Ok(())
 	}
 
+	/// Allocates the buffers needed by the enabled features and clears
+	/// them along with the stats. Returns whether the frame is deferred.
+	fn begin_frame(&mut self) -> bool {
+		// Debug views replace the shaded color, so they always go through
+		// the forward path.
+		let deferred =
+			self.pipeline == Pipeline::Deferred && self.debug_view == DebugView::Off;
+
+		self
+			.buffers
+			.track_overdraw(self.debug_view == DebugView::Overdraw);
+		self.buffers.track_g_buffer(deferred);
+		self.buffers.track_ao(self.ssao.is_some());
+		self.buffers.track_ids(self.picking);
+		self.buffers.set_reverse_z(self.reverse_z);
+		self.reset_buffers();
+
+		self.frame_uniforms.clear();
+		self.stats.reset();
+
+		deferred
+	}
+
+	/// Runs every pass of the frame for a single view, restricted to its
+	/// scissor rectangle which is cleared first when `clear` is set. The
+	/// grid, outline, debug lines and gizmo are drawn when `overlays` is
+	/// set.
+	fn render_view(
+		&mut self,
+		scene: &Scene,
+		view: &View<'_>,
+		deferred: bool,
+		clear: bool,
+		overlays: bool,
+	) {
+		// Nothing can be drawn, the projection would not be finite either.
+		if view.viewport.is_empty() || view.clip_rect(self.win_size).is_empty() {
+			return;
+		}
+
+		// The transformed vertices of the previous view don't apply.
+		self.buffers.vertex_cache.clear();
+
+		let mut draw_call = DrawCall::submit_draw_call(scene, view, self.win_size);
+		draw_call.set_debug_view(self.debug_view);
+		draw_call.set_reverse_z(view.camera, self.reverse_z);
+		draw_call.set_selection(self.selection);
+		draw_call.sort_front_to_back();
+
+		let uniforms = *draw_call.uniforms();
+		self.frame_uniforms.push(uniforms);
+
+		self.buffers.track_stencil(draw_call.uses_stencil());
+
+		if clear {
+			self
+				.buffers
+				.clear_rect(&uniforms.screen.scissor, self.win_size.width);
+		}
+
+		// The forward path shades straight away, so the occlusion needs the
+		// depth of the whole frame from a pre-pass first.
+		let pre_pass = self.depth_pre_pass || (self.ssao.is_some() && !deferred);
+
+		if pre_pass {
+			// Counters of the pre-pass would duplicate the ones of the color
+			// pass, only its time is kept.
+			let mut pre_pass_stats = RenderStats::default();
+
+			draw_call.execute(
+				&mut self.buffers,
+				&mut pre_pass_stats,
+				raster::consume_draw_call_depth,
+			);
+			draw_call.set_depth_pre_pass(true);
+
+			self.stats.timings.pre_pass += pre_pass_stats.timings.total();
+
+			// The deferred pipeline resolves the occlusion after its geometry
+			// pass and applies it while lighting.
+			if let Some(settings) = &self.ssao
+				&& !deferred
+			{
+				let mut stopwatch = Stopwatch::start();
+				ssao::occlusion_pass(&mut self.buffers, &uniforms, settings);
+				self.stats.timings.ssao += stopwatch.lap();
+			}
+		}
+
+		if deferred {
+			draw_call.execute(
+				&mut self.buffers,
+				&mut self.stats,
+				raster::consume_draw_call_deferred,
+			);
+		} else {
+			draw_call.execute(
+				&mut self.buffers,
+				&mut self.stats,
+				raster::consume_draw_call,
+			);
+		}
+
+		let mut stopwatch = Stopwatch::start();
+
+		if deferred {
+			if let Some(settings) = &self.ssao {
+				ssao::occlusion_pass(&mut self.buffers, &uniforms, settings);
+				self.stats.timings.ssao += stopwatch.lap();
+			}
+
+			// The scene holds a single light for now, the pass itself
+			// accumulates any number of them.
+			let lights = [uniforms.light];
+
+			self.stats.fragments_lit += deferred::lighting_pass(
+				&mut self.buffers,
+				&BlinnPhong,
+				&lights,
+				&uniforms,
+			);
+			self.stats.timings.lighting += stopwatch.lap();
+		}
+
+		// Debug views show raw values which must not bleed.
+		if self.debug_view == DebugView::Off {
+			if let Some(settings) = &self.dof {
+				post::dof::dof_pass(
+					&mut self.buffers,
+					&uniforms,
+					view.camera,
+					settings,
+				);
+			}
+
+			if let Some(settings) = &self.bloom {
+				post::bloom::bloom_pass(&mut self.buffers, &uniforms, settings);
+			}
+		}
+
+		post::encode_pass(&mut self.buffers, &uniforms);
+
+		if let Some(grading) = &self.grading
+			&& self.debug_view == DebugView::Off
+		{
+			post::grade::grade_pass(&mut self.buffers, &uniforms, grading);
+		}
+
+		if let Some(quality) = self.fxaa
+			&& self.debug_view == DebugView::Off
+		{
+			post::fxaa::fxaa_pass(&mut self.buffers, &uniforms, quality);
+		}
+
+		if self.debug_view == DebugView::Overdraw {
+			debug::resolve_overdraw(&mut self.buffers, &uniforms);
+		}
+
+		if overlays {
+			self.draw_overlays(&uniforms);
+		}
+
+		self.stats.timings.post += stopwatch.lap();
+	}
+
+	fn draw_overlays(&mut self, uniforms: &GlobalUniforms) {
+		if let Some(settings) = &self.grid {
+			overlay::grid_pass(&mut self.buffers, uniforms, settings);
+		}
+
+		if self.selection.is_some() {
+			overlay::outline_pass(&mut self.buffers, uniforms, &self.outline);
+		}
+
+		self.debug_draw.render(&mut self.buffers, uniforms);
+
+		if self.axis_gizmo {
+			overlay::axis_gizmo(&mut self.buffers, uniforms, AXIS_GIZMO_SIZE);
+		}
+	}
+
 	pub fn reset_buffers(&mut self) {
 		self.buffers.reset();
 	}
@@ -59,9 +374,167 @@ impl Renderer {
 		self.win_size.height = height;
 		self.win_size.width = width;
 		self.buffers.resize(width, height);
+		self.frame_uniforms.clear();
 	}
 
 	pub fn win_size(&self) -> &WinSize {
 		&self.win_size
 	}
+
+	pub fn set_debug_view(&mut self, view: DebugView) {
+		self.debug_view = view;
+	}
+
+	pub fn debug_view(&self) -> DebugView {
+		self.debug_view
+	}
+
+	pub fn set_pipeline(&mut self, pipeline: Pipeline) {
+		self.pipeline = pipeline;
+	}
+
+	pub fn pipeline(&self) -> Pipeline {
+		self.pipeline
+	}
+
+	/// Enables a depth-only pass before the color pass so that every pixel
+	/// is shaded at most once.
+	pub fn set_depth_pre_pass(&mut self, enabled: bool) {
+		self.depth_pre_pass = enabled;
+	}
+
+	pub fn depth_pre_pass(&self) -> bool {
+		self.depth_pre_pass
+	}
+
+	/// Enables screen-space ambient occlusion with the given settings,
+	/// `None` disables it.
+	pub fn set_ssao(&mut self, settings: Option<SsaoSettings>) {
+		self.ssao = settings;
+	}
+
+	pub fn ssao(&self) -> Option<&SsaoSettings> {
+		self.ssao.as_ref()
+	}
+
+	/// Enables the glow around the bright parts of the frame with the given
+	/// settings, `None` disables it.
+	pub fn set_bloom(&mut self, settings: Option<BloomSettings>) {
+		self.bloom = settings;
+	}
+
+	pub fn bloom(&self) -> Option<&BloomSettings> {
+		self.bloom.as_ref()
+	}
+
+	/// Blurs the frame away from the focus plane of the camera lens with
+	/// the given settings, `None` disables it.
+	pub fn set_dof(&mut self, settings: Option<DofSettings>) {
+		self.dof = settings;
+	}
+
+	pub fn dof(&self) -> Option<&DofSettings> {
+		self.dof.as_ref()
+	}
+
+	/// Smooths the aliased edges of the frame with FXAA at the given
+	/// quality, `None` disables it. The overlays are drawn afterwards and
+	/// keep their own anti-aliasing.
+	pub fn set_fxaa(&mut self, quality: Option<FxaaQuality>) {
+		self.fxaa = quality;
+	}
+
+	pub fn fxaa(&self) -> Option<FxaaQuality> {
+		self.fxaa
+	}
+
+	/// Grades the encoded frame through a color look up table, `None`
+	/// disables it. Runs before the anti-aliasing.
+	pub fn set_grading(&mut self, grading: Option<ColorGrading>) {
+		self.grading = grading;
+	}
+
+	pub fn grading(&self) -> Option<&ColorGrading> {
+		self.grading.as_ref()
+	}
+
+	/// Helper geometry drawn over the next frame.
+	pub fn debug_draw(&mut self) -> &mut DebugDraw {
+		&mut self.debug_draw
+	}
+
+	/// Shows the ground grid with the given settings, `None` hides it.
+	pub fn set_grid(&mut self, settings: Option<GridSettings>) {
+		self.grid = settings;
+	}
+
+	pub fn grid(&self) -> Option<&GridSettings> {
+		self.grid.as_ref()
+	}
+
+	/// Shows the world axes in the bottom left corner of the frame.
+	pub fn set_axis_gizmo(&mut self, enabled: bool) {
+		self.axis_gizmo = enabled;
+	}
+
+	pub fn axis_gizmo(&self) -> bool {
+		self.axis_gizmo
+	}
+
+	/// Uses a reverse-Z projection with an infinite far plane, keeping the
+	/// depth precision for distant geometry. Material depth compare
+	/// functions are flipped accordingly.
+	pub fn set_reverse_z(&mut self, enabled: bool) {
+		self.reverse_z = enabled;
+	}
+
+	pub fn reverse_z(&self) -> bool {
+		self.reverse_z
+	}
+
+	/// Keeps the object and triangle index of every pixel so the frame can
+	/// be queried with `pick`.
+	pub fn set_picking(&mut self, enabled: bool) {
+		self.picking = enabled;
+	}
+
+	pub fn picking(&self) -> bool {
+		self.picking
+	}
+
+	/// Surface under the pixel of the last rendered frame, requires
+	/// picking to be enabled before rendering. Overlapping views resolve
+	/// to the one drawn last.
+	pub fn pick(&self, x: u32, y: u32) -> Option<Pick> {
+		let uniforms = self
+			.frame_uniforms
+			.iter()
+			.rev()
+			.find(|uniforms| uniforms.screen.scissor.contains(x, y))?;
+
+		picking::pick(&self.buffers, uniforms, x, y)
+	}
+
+	/// Outlines the object at the given scene index, `None` clears the
+	/// selection. Objects are indexed in order, followed by the instances.
+	pub fn set_selection(&mut self, object: Option<usize>) {
+		self.selection = object;
+	}
+
+	pub fn selection(&self) -> Option<usize> {
+		self.selection
+	}
+
+	pub fn set_outline(&mut self, settings: OutlineSettings) {
+		self.outline = settings;
+	}
+
+	pub fn outline(&self) -> &OutlineSettings {
+		&self.outline
+	}
+
+	/// Statistics of the last rendered frame.
+	pub fn stats(&self) -> &RenderStats {
+		&self.stats
+	}
 }
@@ -1,15 +1,24 @@
 use {
-	crate::shaders::{FS, GVaryings, VS, Varyings, VertexIn, VertexOut},
+	crate::shaders::{
+		DS, FS, GVaryings, Surface, VS, Varyings, VertexIn, VertexOut,
+		uniform::LightUniforms,
+	},
 	pcore::{
 		color::Color,
-		math::{self, Matrix3, Vector4},
+		geometry::UV,
+		math::{self, Matrix3, Vector3, Vector4},
 	},
 	pscene::{
 		// color::Color,
-		texture::TextureSampler,
+		texture::{HeightMap, TextureSampler},
 	},
 };
 
+/// Layers of the parallax ray march when looking at the surface head on
+/// and at a grazing angle.
+const PARALLAX_MIN_LAYERS: f32 = 8.0;
+const PARALLAX_MAX_LAYERS: f32 = 32.0;
+
 pub struct Flat;
 
 impl VS for Flat {
@@ -24,7 +33,6 @@ impl VS for Flat {
 
 		let w_pos =
 			(m_model * Vector4::from((input.attributes.position, 1.0))).xyz();
-		let w_normal = (m_normal * Vector4::from((input.face_normal, 0.0))).xyz();
 		let w_tangent =
 			(m_normal * Vector4::from((input.attributes.tangent, 0.0))).xyz();
 		let w_bitangent =
@@ -36,10 +44,12 @@ impl VS for Flat {
 			clip: m_mvp * Vector4::from((input.attributes.position, 1.0)),
 			vary: Varyings {
 				uv: input.attributes.uv,
-				normal: w_normal,
+				// Set to the face normal once the triangle is assembled.
+				normal: Vector3::ZERO,
 				tangent: w_tangent,
 				bi_tangent: w_bitangent,
 				world_pos: w_pos,
+				color: input.attributes.color.into(),
 				intensity: 0.0,
 			},
 		}
@@ -56,9 +66,21 @@ impl VS for Flat {
 			tangent: input.tangent * raster_in.inv_w,
 			bi_tangent: input.bi_tangent * raster_in.inv_w,
 			world_pos: input.world_pos * raster_in.inv_w,
+			color: input.color * raster_in.inv_w,
 			intensity: input.intensity * raster_in.inv_w,
 		}
 	}
+
+	#[inline(always)]
+	fn assemble_triangle(
+		&self,
+		varyings: &mut [Varyings; 3],
+		face_normal: Vector3,
+	) {
+		for vertex in varyings {
+			vertex.normal = face_normal;
+		}
+	}
 }
 
 impl FS for Flat {
@@ -70,7 +92,12 @@ impl FS for Flat {
 	) -> Color {
 		let material = object.model.material;
 
-		let ng = input.normal.normalize();
+		// Two-sided lighting, back faces are lit from their own side.
+		let ng = if uniforms.primitive.back_facing {
+			uniforms.face_normal * -1.0
+		} else {
+			uniforms.face_normal
+		};
 
 		let u = input.uv.x;
 		let v = input.uv.y;
@@ -92,7 +119,7 @@ impl FS for Flat {
 
 			n_world.normalize()
 		} else {
-			// Geometric normal transformed into world in vertex stage
+			// Geometric normal of the triangle
 			ng
 		};
 
@@ -102,11 +129,13 @@ impl FS for Flat {
 			albedo.bi_sample(u, v, lod)
 		} else {
 			material.diffuse
-		};
+		} * object.tint
+			* Color::from(input.color);
 
 		let light_dir = uniforms.light.direction.normalize();
 
-		let ambient = color * material.ambient * uniforms.light.ambient;
+		let ambient =
+			color * material.ambient * uniforms.light.ambient * uniforms.occlusion;
 
 		let i_ng = ng.dot(&light_dir).max(0.0);
 		let i_np = np_world.dot(&light_dir).max(0.0);
@@ -126,6 +155,7 @@ impl FS for Flat {
 		let uvs = (input[0].uv, input[1].uv, input[2].uv);
 		let w_pos = (input[0].world_pos, input[1].world_pos, input[2].world_pos);
 		let ints = (input[0].intensity, input[1].intensity, input[2].intensity);
+		let colors = (input[0].color, input[1].color, input[2].color);
 
 		Varyings {
 			uv: math::perspective_interpolate(bary, inv_depth, uvs),
@@ -133,6 +163,7 @@ impl FS for Flat {
 			tangent: input[0].tangent,
 			bi_tangent: input[0].bi_tangent,
 			world_pos: math::perspective_interpolate(bary, inv_depth, w_pos),
+			color: math::perspective_interpolate(bary, inv_depth, colors),
 			intensity: math::perspective_interpolate(bary, inv_depth, ints),
 		}
 	}
@@ -146,6 +177,7 @@ impl FS for Flat {
 		Varyings {
 			uv: g_varyings.uv.sample_at(dx, dy),
 			world_pos: g_varyings.world_pos.sample_at(dx, dy),
+			color: g_varyings.color.sample_at(dx, dy),
 			normal: g_varyings.normal.a,
 			tangent: g_varyings.tangent.a,
 			bi_tangent: g_varyings.bi_tangent.a,
@@ -156,17 +188,20 @@ impl FS for Flat {
 	fn step_horizontal(&self, g_varyings: &GVaryings, varyings: &mut Varyings) {
 		g_varyings.uv.step_x(&mut varyings.uv);
 		g_varyings.world_pos.step_x(&mut varyings.world_pos);
+		g_varyings.color.step_x(&mut varyings.color);
 	}
 
 	fn step_vertical(&self, g_varyings: &GVaryings, varyings: &mut Varyings) {
 		g_varyings.uv.step_y(&mut varyings.uv);
 		g_varyings.world_pos.step_y(&mut varyings.world_pos);
+		g_varyings.color.step_y(&mut varyings.color);
 	}
 
 	fn recover_value(&self, varyings: &Varyings, inv_w: f32) -> Varyings {
 		Varyings {
 			uv: varyings.uv * inv_w,
 			world_pos: varyings.world_pos * inv_w,
+			color: varyings.color * inv_w,
 			normal: varyings.normal,
 			tangent: varyings.tangent,
 			bi_tangent: varyings.bi_tangent,
@@ -216,82 +251,215 @@ impl VS for BlinnPhong {
 				tangent,
 				bi_tangent,
 				world_pos,
+				color: input.attributes.color.into(),
 				intensity: 0.0,
 			},
 		}
 	}
 }
 
-impl FS for BlinnPhong {
-	fn shade_pixel<'d>(
+impl DS for BlinnPhong {
+	fn shade_surface<'d>(
 		&self,
 		input: Varyings,
 		object: pscene::object::ObjectRef<'d>,
 		uniforms: &super::uniform::GlobalUniforms,
-	) -> Color {
+	) -> Surface {
 		let material = object.model.material;
 
-		let ng = input.normal.normalize();
+		// Two-sided lighting, back faces are lit from their own side.
+		let ng = if uniforms.primitive.back_facing {
+			input.normal.normalize() * -1.0
+		} else {
+			input.normal.normalize()
+		};
 
-		let u = input.uv.x;
-		let v = input.uv.y;
+		// Tangent and bi-tangent, only needed by the texture space maps.
+		let tangent_frame =
+			(material.normal.is_some() || material.height.is_some()).then(|| {
+				let t = input.tangent.normalize();
+				let b = input.bi_tangent.normalize();
+
+				// T = normalize(T - N * dot(T, N))
+				// B = cross(N, T)
+				let t = (t - ng * t.dot(&ng)).normalize();
+				let b = (b - ng * b.dot(&ng)).normalize();
+
+				(t, b)
+			});
+
+		// Parallax shifted texture coordinates, sampled by every map below.
+		let uv = match (material.height, tangent_frame) {
+			(Some(h_map), Some((t, b))) => {
+				// V (View direction) in tangent space
+				let view_dir = (uniforms.camera.position - input.world_pos).normalize();
+				let view_ts =
+					Vector3::new(view_dir.dot(&t), view_dir.dot(&b), view_dir.dot(&ng));
+
+				let lod = uniforms.lods.height.unwrap_or(0.0);
+
+				parallax_uv(h_map, material.height_scale, input.uv, view_ts, lod)
+			}
+			_ => input.uv,
+		};
 
-		let np_world = if let Some(n_map) = material.normal {
-			let t = input.tangent.normalize();
-			let b = input.bi_tangent.normalize();
+		let u = uv.x;
+		let v = uv.y;
 
-			// T = normalize(T - N * dot(T, N))
-			// B = cross(N, T)
-			let t = (t - ng * t.dot(&ng)).normalize();
-			let b = (b - ng * b.dot(&ng)).normalize();
+		let np_world =
+			if let (Some(n_map), Some((t, b))) = (material.normal, tangent_frame) {
+				let tbn = Matrix3::from_tbn(t, b, ng);
 
-			let tbn = Matrix3::from_tbn(t, b, ng);
+				let lod = uniforms.lods.normal.unwrap_or(0.0);
 
-			let lod = uniforms.lods.normal.unwrap_or(0.0);
+				// N (perpatuated world normal)
+				let n_world = tbn * n_map.bi_sample(u, v, lod);
 
-			// N (perpatuated world normal)
-			let n_world = tbn * n_map.bi_sample(u, v, lod);
+				n_world.normalize()
+			} else {
+				// N (Geometric normal already transformed into world in vertex stage)
+				ng
+			};
 
-			n_world.normalize()
-		} else {
-			// N (Geometric normal already transformed into world in vertex stage)
-			ng
-		};
+		let lods = &uniforms.lods;
 
 		let color = if let Some(albedo) = material.albedo {
-			let lod = uniforms.lods.albedo.unwrap_or(0.0);
-			albedo.tri_sample(u, v, lod)
+			albedo.tri_sample(u, v, lods.albedo.unwrap_or(0.0))
 		} else {
 			material.diffuse
 		};
 
+		let specular = if let Some(s_map) = material.specular_map {
+			material.specular * s_map.bi_sample(u, v, lods.specular.unwrap_or(0.0))
+		} else {
+			material.specular
+		};
+
+		let occlusion = if let Some(ao_map) = material.occlusion_map {
+			uniforms.occlusion * ao_map.bi_sample(u, v, lods.occlusion.unwrap_or(0.0))
+		} else {
+			uniforms.occlusion
+		};
+
+		let emissive = if let Some(e_map) = material.emissive_map {
+			e_map.tri_sample(u, v, lods.emissive.unwrap_or(0.0))
+		} else {
+			material.emissive
+		};
+
+		Surface {
+			position: input.world_pos,
+			normal: np_world,
+			albedo: color * object.tint * Color::from(input.color),
+			ambient: material.ambient * occlusion,
+			specular,
+			shininess: material.shininess,
+			emissive,
+		}
+	}
+
+	fn shade_light(
+		&self,
+		surface: &Surface,
+		light: &LightUniforms,
+		uniforms: &super::uniform::GlobalUniforms,
+	) -> Color {
+		let color = surface.albedo;
+		let np_world = surface.normal;
+
 		// L (Light didrection)
-		let light_dir = uniforms.light.direction;
+		let light_dir = light.direction;
 
 		// V (View direction)
-		let view_dir = (uniforms.camera.position - input.world_pos).normalize();
+		let view_dir = (uniforms.camera.position - surface.position).normalize();
 
 		// H = normalize(L + V)
 		let half_vec = (light_dir + view_dir).normalize();
 
 		// Diffuse
-		let diff = color * uniforms.light.color * np_world.dot(&light_dir).max(0.0);
+		let diff = color * light.color * np_world.dot(&light_dir).max(0.0);
 
-		let ambient = color * material.ambient * uniforms.light.ambient;
+		let ambient = color * surface.ambient * light.ambient;
 
 		// Specular factor, pow(max(dot(N, H), 0), shininess)
 		// The specular factor here is calculated uisng modified
 		// Schlick approximation to avoid the powf in this hot
 		// pixel loop.
-		let s = material.shininess;
+		let s = surface.shininess;
 		let ndoth = np_world.dot(&half_vec).max(0.0);
 		let spec_factor = ndoth / (s - s * ndoth + ndoth);
 
 		// Specular
-		let specular = material.specular * uniforms.light.color * spec_factor;
+		let specular = surface.specular * light.color * spec_factor;
 
 		ambient + diff + specular
 	}
+}
+
+/// Parallax occlusion mapping, marches the view ray through the relief of
+/// the height map in layers and returns the texture coordinates where it
+/// first hits the surface. `view_ts` is the tangent space direction towards
+/// the camera.
+#[inline]
+fn parallax_uv(
+	h_map: &HeightMap,
+	scale: f32,
+	uv: UV,
+	view_ts: Vector3,
+	lod: f32,
+) -> UV {
+	if scale <= 0.0 || view_ts.z <= 0.0 {
+		return uv;
+	}
+
+	// More layers at grazing angles where the ray travels further.
+	let layers = math::lerp(PARALLAX_MAX_LAYERS, PARALLAX_MIN_LAYERS, view_ts.z);
+	let layer_depth = 1.0 / layers;
+
+	// Texture space shift per layer, the view angle is clamped so the
+	// offsets stay bounded close to the silhouettes.
+	let shift = UV::new(view_ts.x, view_ts.y) * (scale / view_ts.z.max(0.1));
+	let delta = shift * layer_depth;
+
+	// Depth below the surface, the inverse of the height.
+	let depth_at = |uv: UV| 1.0 - h_map.bi_sample(uv.x, uv.y, lod);
+
+	let mut current_uv = uv;
+	let mut current_depth = depth_at(current_uv);
+	let mut layer = 0.0;
+
+	while layer < current_depth && layer < 1.0 {
+		current_uv = current_uv - delta;
+		current_depth = depth_at(current_uv);
+		layer += layer_depth;
+	}
+
+	// Interpolates between the layers before and after the hit.
+	let previous_uv = current_uv + delta;
+
+	let after = current_depth - layer;
+	let before = depth_at(previous_uv) - layer + layer_depth;
+
+	let denominator = after - before;
+	if denominator.abs() <= f32::EPSILON {
+		return current_uv;
+	}
+
+	let weight = after / denominator;
+
+	previous_uv * weight + current_uv * (1.0 - weight)
+}
+
+impl FS for BlinnPhong {
+	fn shade_pixel<'d>(
+		&self,
+		input: Varyings,
+		object: pscene::object::ObjectRef<'d>,
+		uniforms: &super::uniform::GlobalUniforms,
+	) -> Color {
+		let surface = self.shade_surface(input, object, uniforms);
+		self.shade_light(&surface, &uniforms.light, uniforms) + surface.emissive
+	}
 
 	fn perspective_interpolate(
 		&self,
@@ -1,5 +1,6 @@
 use {
 	pcore::{
+		color::Color,
 		geometry::{BiTangent, Normal, Tangent, UV, VertexAttributes},
 		math::{Gradient, Vector2, Vector3, Vector4},
 	},
@@ -9,7 +10,6 @@ use {
 #[derive(Debug, Clone, Copy)]
 pub struct VertexIn {
 	pub attributes: VertexAttributes,
-	pub face_normal: Vector3,
 }
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -19,6 +19,8 @@ pub struct Varyings {
 	pub tangent: Tangent,
 	pub bi_tangent: BiTangent,
 	pub world_pos: Vector3,
+	/// Vertex color as RGBA, all four channels are interpolated.
+	pub color: Vector4,
 	pub intensity: f32,
 }
 
@@ -33,6 +35,7 @@ impl Mul<f32> for Varyings {
 			tangent: self.tangent * rhs,
 			bi_tangent: self.bi_tangent * rhs,
 			world_pos: self.world_pos * rhs,
+			color: self.color * rhs,
 			intensity: self.intensity * rhs,
 		}
 	}
@@ -48,6 +51,7 @@ impl Mul for Varyings {
 			tangent: self.tangent * rhs.tangent,
 			bi_tangent: self.bi_tangent * rhs.bi_tangent,
 			world_pos: self.world_pos * rhs.world_pos,
+			color: self.color * rhs.color,
 			intensity: self.intensity * rhs.intensity,
 		}
 	}
@@ -64,6 +68,7 @@ impl Add for Varyings {
 			tangent: self.tangent + rhs.tangent,
 			bi_tangent: self.bi_tangent + rhs.bi_tangent,
 			world_pos: self.world_pos + rhs.world_pos,
+			color: self.color + rhs.color,
 			intensity: self.intensity + rhs.intensity,
 		}
 	}
@@ -80,11 +85,26 @@ impl Sub for Varyings {
 			tangent: self.tangent - rhs.tangent,
 			bi_tangent: self.bi_tangent - rhs.bi_tangent,
 			world_pos: self.world_pos - rhs.world_pos,
+			color: self.color - rhs.color,
 			intensity: self.intensity - rhs.intensity,
 		}
 	}
 }
 
+/// Material inputs of a visible fragment, everything the lighting stage
+/// needs once the geometry is resolved.
+#[derive(Clone, Copy)]
+pub struct Surface {
+	pub position: Vector3,
+	pub normal: Normal,
+	pub albedo: Color,
+	pub ambient: Color,
+	pub specular: Color,
+	pub shininess: f32,
+	/// Light emitted by the surface, added once on top of all the lights.
+	pub emissive: Color,
+}
+
 #[derive(Default, Debug, Clone, Copy)]
 pub struct VertexOut {
 	pub clip: Vector4,
@@ -98,6 +118,7 @@ pub struct GVaryings {
 	pub tangent: Gradient<Tangent>,
 	pub bi_tangent: Gradient<BiTangent>,
 	pub world_pos: Gradient<Vector3>,
+	pub color: Gradient<Vector4>,
 }
 
 impl GVaryings {
@@ -128,6 +149,7 @@ impl GVaryings {
 				s,
 				inv_det,
 			),
+			color: Gradient::new([v[0].color, v[1].color, v[2].color], s, inv_det),
 		}
 	}
 
@@ -138,6 +160,7 @@ impl GVaryings {
 		self.tangent.step_x(&mut varyings.tangent);
 		self.bi_tangent.step_x(&mut varyings.bi_tangent);
 		self.world_pos.step_x(&mut varyings.world_pos);
+		self.color.step_x(&mut varyings.color);
 	}
 
 	#[inline(always)]
@@ -147,6 +170,7 @@ impl GVaryings {
 		self.tangent.step_y(&mut varyings.tangent);
 		self.bi_tangent.step_y(&mut varyings.bi_tangent);
 		self.world_pos.step_y(&mut varyings.world_pos);
+		self.color.step_y(&mut varyings.color);
 	}
 
 	pub fn sample_all(&self, dx: f32, dy: f32) -> Varyings {
@@ -156,6 +180,7 @@ impl GVaryings {
 			tangent: self.tangent.sample_at(dx, dy),
 			bi_tangent: self.bi_tangent.sample_at(dx, dy),
 			world_pos: self.world_pos.sample_at(dx, dy),
+			color: self.color.sample_at(dx, dy),
 			intensity: 0.0,
 		}
 	}
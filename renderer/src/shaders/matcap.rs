@@ -0,0 +1,138 @@
+use {
+	crate::{
+		raster::RasterIn,
+		shaders::{
+			BlinnPhong, FS, GVaryings, VS, Varyings, VertexIn, VertexOut,
+			uniform::GlobalUniforms,
+		},
+	},
+	pcore::{
+		color::Color,
+		math::{Matrix3, Vector3, Vector4},
+	},
+	pscene::{object::ObjectRef, texture::TextureSampler},
+};
+
+/// Unlit shading from a matcap. The view space normal, normal-mapped when
+/// the material has a normal map, picks the texel of the lit sphere, the
+/// scene lights are ignored.
+pub struct Matcap;
+
+impl VS for Matcap {
+	fn shade_vertex<'d>(
+		&self,
+		input: VertexIn,
+		object: ObjectRef<'d>,
+		uniforms: &GlobalUniforms,
+	) -> VertexOut {
+		BlinnPhong.shade_vertex(input, object, uniforms)
+	}
+
+	fn perspective_divide(
+		&self,
+		input: Varyings,
+		raster_in: &RasterIn,
+	) -> Varyings {
+		BlinnPhong.perspective_divide(input, raster_in)
+	}
+}
+
+impl FS for Matcap {
+	fn shade_pixel<'d>(
+		&self,
+		input: Varyings,
+		object: ObjectRef<'d>,
+		uniforms: &GlobalUniforms,
+	) -> Color {
+		let material = object.model.material;
+
+		let ng = if uniforms.primitive.back_facing {
+			input.normal.normalize() * -1.0
+		} else {
+			input.normal.normalize()
+		};
+
+		let n_world = match material.normal {
+			Some(n_map) => {
+				// T = normalize(T - N * dot(T, N))
+				// B = normalize(B - N * dot(B, N))
+				let t = input.tangent.normalize();
+				let b = input.bi_tangent.normalize();
+				let t = (t - ng * t.dot(&ng)).normalize();
+				let b = (b - ng * b.dot(&ng)).normalize();
+
+				let lod = uniforms.lods.normal.unwrap_or(0.0);
+				let n = n_map.bi_sample(input.uv.x, input.uv.y, lod);
+
+				(Matrix3::from_tbn(t, b, ng) * n).normalize()
+			}
+			None => ng,
+		};
+
+		let n = (uniforms.m_view * Vector4::from((n_world, 0.0)))
+			.xyz()
+			.normalize();
+
+		let color = match material.matcap {
+			Some(matcap) => {
+				let eye = (uniforms.m_view * Vector4::from((input.world_pos, 1.0)))
+					.xyz()
+					.normalize();
+
+				let (u, v) = sphere_uv(eye, n);
+				matcap.bi_sample(u, v, 0.0)
+			}
+			// Plain facing ratio until a matcap is bound.
+			None => material.diffuse * n.z.max(0.0),
+		};
+
+		color * object.tint * Color::from(input.color)
+	}
+
+	fn perspective_interpolate(
+		&self,
+		input: [Varyings; 3],
+		bary: (f32, f32, f32),
+		inv_depth: f32,
+	) -> Varyings {
+		BlinnPhong.perspective_interpolate(input, bary, inv_depth)
+	}
+
+	fn sample_gradients(
+		&self,
+		g_varyings: &GVaryings,
+		dx: f32,
+		dy: f32,
+	) -> Varyings {
+		g_varyings.sample_all(dx, dy)
+	}
+
+	fn recover_value(&self, varyings: &Varyings, inv_w: f32) -> Varyings {
+		*varyings * inv_w
+	}
+
+	fn step_horizontal(&self, g_varyings: &GVaryings, varyings: &mut Varyings) {
+		g_varyings.step_horizontal_all(varyings);
+	}
+
+	fn step_vertical(&self, g_varyings: &GVaryings, varyings: &mut Varyings) {
+		g_varyings.step_vertical_all(varyings);
+	}
+}
+
+/// Sphere map coordinates of the view space normal `n` seen along `eye`.
+/// The normal is reflected around the view ray rather than used as is, so
+/// surfaces off the center of the screen don't stretch the matcap.
+#[inline]
+fn sphere_uv(eye: Vector3, n: Vector3) -> (f32, f32) {
+	// R = E - 2 * dot(E, N) * N
+	let r = eye - n * (2.0 * eye.dot(&n));
+	let m = 2.0 * (r.x * r.x + r.y * r.y + (r.z + 1.0) * (r.z + 1.0)).sqrt();
+
+	// Exactly on the silhouette, the reflection runs along the view ray.
+	if m <= f32::EPSILON {
+		return (n.x * 0.5 + 0.5, n.y * 0.5 + 0.5);
+	}
+
+	(r.x / m + 0.5, r.y / m + 0.5)
+}
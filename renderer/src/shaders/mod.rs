@@ -1,12 +1,18 @@
 use {
 	crate::raster::RasterIn,
-	pcore::{color::Color, math::Vector2},
+	pcore::{
+		color::Color,
+		math::{Vector2, Vector3, Vector4},
+	},
 	pscene::object::ObjectRef,
+	uniform::LightUniforms,
 };
-pub use {effects::*, io::*};
+pub use {effects::*, io::*, matcap::*, toon::*};
 
 mod effects;
 pub mod io;
+mod matcap;
+mod toon;
 pub mod uniform;
 
 pub trait VS {
@@ -22,6 +28,39 @@ pub trait VS {
 		input: Varyings,
 		raster_in: &RasterIn,
 	) -> Varyings;
+
+	/// Clip space position alone, for the passes which only need the
+	/// depth. It must match the position of `shade_vertex`.
+	#[inline(always)]
+	fn shade_position<'d>(
+		&self,
+		input: VertexIn,
+		object: ObjectRef<'d>,
+		uniforms: &uniform::GlobalUniforms,
+	) -> Vector4 {
+		let m_mvp = uniforms.m_projection * uniforms.m_view * object.m_model;
+
+		m_mvp * Vector4::from((input.attributes.position, 1.0))
+	}
+
+	/// Material inputs of the positions besides the mesh and transform,
+	/// transformed positions are only shared between objects with the same
+	/// salt.
+	#[inline(always)]
+	fn cache_salt(&self, _object: ObjectRef<'_>) -> u32 {
+		0
+	}
+
+	/// Adjusts the varyings of the vertices of a triangle once it is
+	/// assembled, `face_normal` being its world space geometric normal.
+	/// The vertex stage runs once per shared vertex and can't see it.
+	#[inline(always)]
+	fn assemble_triangle(
+		&self,
+		_varyings: &mut [Varyings; 3],
+		_face_normal: Vector3,
+	) {
+	}
 }
 
 pub trait FS {
@@ -61,3 +100,21 @@ pub trait FS {
 
 	fn step_vertical(&self, g_varyings: &GVaryings, varyings: &mut Varyings);
 }
+
+/// Fragment stage split in two for the deferred pipeline: the surface
+/// is resolved during rasterization and lit later, once per visible pixel.
+pub trait DS {
+	fn shade_surface<'d>(
+		&self,
+		input: Varyings,
+		object: ObjectRef<'d>,
+		uniforms: &uniform::GlobalUniforms,
+	) -> Surface;
+
+	fn shade_light(
+		&self,
+		surface: &Surface,
+		light: &LightUniforms,
+		uniforms: &uniform::GlobalUniforms,
+	) -> Color;
+}
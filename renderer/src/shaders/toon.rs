@@ -0,0 +1,264 @@
+use {
+	crate::{
+		raster::RasterIn,
+		shaders::{
+			BlinnPhong, DS, FS, GVaryings, Surface, VS, Varyings, VertexIn,
+			VertexOut,
+			uniform::{GlobalUniforms, LightUniforms},
+		},
+	},
+	pcore::{
+		color::Color,
+		math::{Vector2, Vector3, Vector4},
+	},
+	pscene::{material::MaterialRef, object::ObjectRef, texture::TextureSampler},
+};
+
+/// Largest u read from a ramp texture, the sampler wraps at 1.
+const RAMP_MAX_U: f32 = 0.999;
+
+/// Cel shading. The surface is resolved like `BlinnPhong`, so every map of
+/// the material applies, then lit with flat diffuse bands, a hard edged
+/// highlight and an optional rim light.
+pub struct Toon;
+
+impl VS for Toon {
+	fn shade_vertex<'d>(
+		&self,
+		input: VertexIn,
+		object: ObjectRef<'d>,
+		uniforms: &GlobalUniforms,
+	) -> VertexOut {
+		BlinnPhong.shade_vertex(input, object, uniforms)
+	}
+
+	fn perspective_divide(
+		&self,
+		input: Varyings,
+		raster_in: &RasterIn,
+	) -> Varyings {
+		BlinnPhong.perspective_divide(input, raster_in)
+	}
+}
+
+impl FS for Toon {
+	fn shade_pixel<'d>(
+		&self,
+		input: Varyings,
+		object: ObjectRef<'d>,
+		uniforms: &GlobalUniforms,
+	) -> Color {
+		let material = &object.model.material;
+
+		let surface = BlinnPhong.shade_surface(input, object, uniforms);
+		shade_light(&surface, material, &uniforms.light, uniforms)
+			+ surface.emissive
+	}
+
+	fn perspective_interpolate(
+		&self,
+		input: [Varyings; 3],
+		bary: (f32, f32, f32),
+		inv_depth: f32,
+	) -> Varyings {
+		BlinnPhong.perspective_interpolate(input, bary, inv_depth)
+	}
+
+	fn sample_gradients(
+		&self,
+		g_varyings: &GVaryings,
+		dx: f32,
+		dy: f32,
+	) -> Varyings {
+		g_varyings.sample_all(dx, dy)
+	}
+
+	fn recover_value(&self, varyings: &Varyings, inv_w: f32) -> Varyings {
+		*varyings * inv_w
+	}
+
+	fn step_horizontal(&self, g_varyings: &GVaryings, varyings: &mut Varyings) {
+		g_varyings.step_horizontal_all(varyings);
+	}
+
+	fn step_vertical(&self, g_varyings: &GVaryings, varyings: &mut Varyings) {
+		g_varyings.step_vertical_all(varyings);
+	}
+}
+
+/// Toon lighting of a single light.
+fn shade_light(
+	surface: &Surface,
+	material: &MaterialRef<'_>,
+	light: &LightUniforms,
+	uniforms: &GlobalUniforms,
+) -> Color {
+	let toon = &material.toon;
+
+	let n = surface.normal;
+	let light_dir = light.direction;
+	let view_dir = (uniforms.camera.position - surface.position).normalize();
+
+	let ndotl = n.dot(&light_dir);
+	let lit = ndotl > 0.0;
+
+	// The ramp covers the whole sphere, the shadow side included, the
+	// bands the range of their thresholds.
+	let shade = match material.toon_ramp {
+		Some(ramp) => {
+			let u = (ndotl * 0.5 + 0.5).clamp(0.0, RAMP_MAX_U);
+			ramp.bi_sample(u, 0.5, 0.0)
+		}
+		None => Color::new_rgb_splat(toon.bands.shade(ndotl)),
+	};
+
+	let diff = surface.albedo * light.color * shade;
+	let ambient = surface.albedo * surface.ambient * light.ambient;
+
+	// Same specular factor as `BlinnPhong`, thresholded into a flat spot.
+	let s = surface.shininess;
+	let half_vec = (light_dir + view_dir).normalize();
+	let ndoth = n.dot(&half_vec).max(0.0);
+	let spec_factor = ndoth / (s - s * ndoth + ndoth);
+
+	let specular = if lit && spec_factor > toon.specular_threshold {
+		surface.specular * light.color
+	} else {
+		Color::BLACK
+	};
+
+	// Rim on the lit side of the silhouette, where the surface turns away
+	// from the camera.
+	let rim = match toon.rim {
+		Some(rim) if lit && n.dot(&view_dir) < rim.width => rim.color * light.color,
+		_ => Color::BLACK,
+	};
+
+	ambient + diff + specular + rim
+}
+
+/// Ink outline of toon materials drawn as an inverted hull, the back faces
+/// of the mesh are pushed outwards along their normals by the outline
+/// width in pixels and filled with the ink color.
+pub struct ToonOutline;
+
+impl VS for ToonOutline {
+	fn shade_vertex<'d>(
+		&self,
+		input: VertexIn,
+		object: ObjectRef<'d>,
+		uniforms: &GlobalUniforms,
+	) -> VertexOut {
+		let mut v_out = BlinnPhong.shade_vertex(input, object, uniforms);
+		v_out.clip = extrude(v_out.clip, v_out.vary.normal, object, uniforms);
+
+		v_out
+	}
+
+	fn perspective_divide(
+		&self,
+		input: Varyings,
+		_raster_in: &RasterIn,
+	) -> Varyings {
+		input
+	}
+
+	fn shade_position<'d>(
+		&self,
+		input: VertexIn,
+		object: ObjectRef<'d>,
+		uniforms: &GlobalUniforms,
+	) -> Vector4 {
+		let clip = BlinnPhong.shade_position(input, object, uniforms);
+		let normal =
+			(object.m_normal * Vector4::from((input.attributes.normal, 0.0))).xyz();
+
+		extrude(clip, normal, object, uniforms)
+	}
+
+	fn cache_salt(&self, object: ObjectRef<'_>) -> u32 {
+		let outline = object.model.material.toon.outline;
+		outline.map_or(0, |outline| outline.width.to_bits())
+	}
+}
+
+/// Pushes the clip space position of a hull vertex outwards along its world
+/// space normal by the outline width, in pixels.
+fn extrude(
+	clip: Vector4,
+	normal: Vector3,
+	object: ObjectRef<'_>,
+	uniforms: &GlobalUniforms,
+) -> Vector4 {
+	let Some(outline) = object.model.material.toon.outline else {
+		return clip;
+	};
+
+	// Screen space direction of the normal, in pixels.
+	let viewport = &uniforms.screen.viewport;
+	let half_size =
+		Vector2::new(viewport.width as f32, viewport.height as f32) * 0.5;
+
+	let n_clip = uniforms.m_view_projection * Vector4::from((normal, 0.0));
+	let n_screen = Vector2::new(n_clip.x, n_clip.y) * half_size;
+
+	if n_screen.magnitude() <= f32::EPSILON {
+		return clip;
+	}
+
+	// Back to clip space, scaled by w to stay constant after the
+	// perspective division.
+	let offset = n_screen.normalize() * (outline.width * clip.w);
+
+	Vector4::new(
+		clip.x + offset.x / half_size.x,
+		clip.y + offset.y / half_size.y,
+		clip.z,
+		clip.w,
+	)
+}
+
+impl FS for ToonOutline {
+	fn shade_pixel<'d>(
+		&self,
+		_input: Varyings,
+		object: ObjectRef<'d>,
+		_uniforms: &GlobalUniforms,
+	) -> Color {
+		object
+			.model
+			.material
+			.toon
+			.outline
+			.map_or(Color::BLACK, |outline| outline.color)
+	}
+
+	// The ink is flat, no varying is interpolated.
+
+	fn perspective_interpolate(
+		&self,
+		input: [Varyings; 3],
+		_bary: (f32, f32, f32),
+		_inv_depth: f32,
+	) -> Varyings {
+		input[0]
+	}
+
+	fn sample_gradients(
+		&self,
+		_g_varyings: &GVaryings,
+		_dx: f32,
+		_dy: f32,
+	) -> Varyings {
+		Varyings::default()
+	}
+
+	fn recover_value(&self, varyings: &Varyings, _inv_w: f32) -> Varyings {
+		*varyings
+	}
+
+	fn step_horizontal(&self, _g_varyings: &GVaryings, _varyings: &mut Varyings) {
+	}
+
+	fn step_vertical(&self, _g_varyings: &GVaryings, _varyings: &mut Varyings) {}
+}
@@ -1,13 +1,18 @@
 use {
-	crate::render::WinSize,
+	crate::{
+		debug::DebugView,
+		render::WinSize,
+		view::{Rect, View},
+	},
 	pcore::{
 		color::Color,
 		math::{Matrix4, Vector3},
 	},
 	pscene::{
-		camera::Camera,
+		camera::{self, Camera},
 		// color::Color,
 		light::Light,
+		material::DepthCompare,
 	},
 };
 
@@ -20,12 +25,81 @@ pub struct GlobalUniforms {
 	pub light: LightUniforms,
 	pub camera: CameraUniforms,
 	pub lods: LOD,
+	/// Ambient occlusion of the current pixel, 1 is unoccluded.
+	pub occlusion: f32,
+	pub primitive: PrimitiveUniforms,
+	/// World space geometric normal of the triangle being rasterized.
+	pub face_normal: Vector3,
+	pub debug: DebugView,
+	pub depth: DepthUniforms,
+	/// Index of the object marked in the stencil for outlining.
+	pub selection: Option<usize>,
+}
+
+impl GlobalUniforms {
+	/// Uniforms of `view` rendered into a target of size `target`, lit by
+	/// `light`.
+	pub fn new(target: WinSize, view: &View, light: &Light) -> Self {
+		let screen = ScreenUniforms::new(target, view);
+		let m_view = view.camera.get_view_matrix();
+		let m_projection = view.camera.get_projection_matrix(screen.aspect);
+
+		Self {
+			m_view,
+			m_projection,
+			m_view_projection: m_projection * m_view,
+			screen,
+			light: LightUniforms::from(light),
+			camera: CameraUniforms::from(view.camera),
+			lods: LOD::default(),
+			occlusion: 1.0,
+			primitive: PrimitiveUniforms::default(),
+			face_normal: Vector3::ZERO,
+			debug: DebugView::default(),
+			depth: DepthUniforms::default(),
+			selection: None,
+		}
+	}
 }
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct LOD {
 	pub albedo: Option<f32>,
 	pub normal: Option<f32>,
+	pub height: Option<f32>,
+	pub emissive: Option<f32>,
+	pub specular: Option<f32>,
+	pub occlusion: Option<f32>,
+}
+
+/// Indices of the primitive currently being rasterized.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrimitiveUniforms {
+	pub object: usize,
+	pub triangle: usize,
+	/// Set while rasterizing a back face which was not culled.
+	pub back_facing: bool,
+}
+
+/// Depth buffer state shared by the passes of a frame.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct DepthUniforms {
+	/// Depth is stored reversed, 1 at the near plane and 0 at infinity.
+	pub reverse_z: bool,
+	/// The depth buffer was filled by a depth pre-pass.
+	pub pre_pass: bool,
+}
+
+impl DepthUniforms {
+	/// Compare function to apply for the depth range in use.
+	#[inline(always)]
+	pub fn resolve(&self, compare: DepthCompare) -> DepthCompare {
+		if self.reverse_z {
+			compare.reversed()
+		} else {
+			compare
+		}
+	}
 }
 
 #[derive(Clone, Copy)]
@@ -50,6 +124,9 @@ impl From<&Light> for LightUniforms {
 #[derive(Debug, Clone, Copy)]
 pub struct CameraUniforms {
 	pub position: Vector3,
+	pub right: Vector3,
+	pub up: Vector3,
+	pub forward: Vector3,
 	pub fov: f32,
 	pub near: f32,
 	pub far: f32,
@@ -59,6 +136,9 @@ impl From<&Camera> for CameraUniforms {
 	fn from(value: &Camera) -> Self {
 		Self {
 			position: value.position,
+			right: value.basis[camera::RIGHT],
+			up: value.basis[camera::UP],
+			forward: value.basis[camera::FORWARD],
 			fov: value.fov,
 			near: value.near,
 			far: value.far,
@@ -68,17 +148,48 @@ impl From<&Camera> for CameraUniforms {
 
 #[derive(Debug, Clone, Copy)]
 pub struct ScreenUniforms {
+	/// Aspect ratio of the viewport.
 	pub aspect: f32,
+	/// Size of the render target, the row stride of the buffers.
 	pub width: f32,
 	pub height: f32,
+	/// Area of the target the normalized device coordinates map to.
+	pub viewport: Rect,
+	/// Pixels which may be written, inside both viewport and target.
+	pub scissor: Rect,
 }
 
-impl From<&WinSize> for ScreenUniforms {
-	fn from(value: &WinSize) -> Self {
+impl ScreenUniforms {
+	pub fn new(target: WinSize, view: &View) -> Self {
 		Self {
-			aspect: value.aspect(),
-			width: value.width as f32,
-			height: value.height as f32,
+			aspect: view.viewport.aspect(),
+			width: target.width as f32,
+			height: target.height as f32,
+			viewport: view.viewport,
+			scissor: view.clip_rect(target),
 		}
 	}
+
+	/// Target pixel position of normalized device coordinates.
+	#[inline(always)]
+	pub fn to_screen(&self, ndc_x: f32, ndc_y: f32) -> (f32, f32) {
+		let viewport = &self.viewport;
+
+		let x = viewport.x as f32 + (ndc_x + 1.0) * 0.5 * viewport.width as f32;
+		let y =
+			viewport.y as f32 + (1.0 - (ndc_y + 1.0) * 0.5) * viewport.height as f32;
+
+		(x, y)
+	}
+
+	/// Normalized device coordinates of a target pixel position.
+	#[inline(always)]
+	pub fn to_ndc(&self, x: f32, y: f32) -> (f32, f32) {
+		let viewport = &self.viewport;
+
+		let ndc_x = (x - viewport.x as f32) / viewport.width as f32 * 2.0 - 1.0;
+		let ndc_y = 1.0 - (y - viewport.y as f32) / viewport.height as f32 * 2.0;
+
+		(ndc_x, ndc_y)
+	}
 }
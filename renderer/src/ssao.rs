@@ -0,0 +1,345 @@
+use {
+	crate::{buffer::Buffers, shaders::uniform::GlobalUniforms},
+	pcore::math::{Matrix4, Vector3, Vector4},
+	std::f32::consts::TAU,
+};
+
+/// Upper bound of the hemisphere kernel size.
+pub const MAX_SAMPLES: u32 = 64;
+
+/// Parameters of the screen-space ambient occlusion pass.
+#[derive(Debug, Clone, Copy)]
+pub struct SsaoSettings {
+	/// World space radius of the sampled hemisphere.
+	pub radius: f32,
+	/// Depth samples per pixel, clamped to `MAX_SAMPLES`.
+	pub samples: u32,
+	/// Radius in pixels of the depth aware blur, 0 disables it.
+	pub blur: u32,
+	/// Exponent applied to the resolved term, higher is darker.
+	pub intensity: f32,
+	/// View space depth offset avoiding self occlusion of flat surfaces.
+	pub bias: f32,
+}
+
+impl Default for SsaoSettings {
+	fn default() -> Self {
+		Self {
+			radius: 0.5,
+			samples: 16,
+			blur: 2,
+			intensity: 1.0,
+			bias: 0.025,
+		}
+	}
+}
+
+/// Computes the ambient occlusion term of every pixel from the depth
+/// buffer into `Buffers::ao`. Normals come from the G-buffer when it is
+/// filled, otherwise they are reconstructed from the depth. Only the
+/// scissor of the view is processed, the buffers below cover just that
+/// rectangle.
+pub fn occlusion_pass(
+	buffers: &mut Buffers,
+	uniforms: &GlobalUniforms,
+	settings: &SsaoSettings,
+) {
+	let stride = uniforms.screen.width as usize;
+	let scissor = uniforms.screen.scissor;
+
+	if scissor.is_empty() {
+		return;
+	}
+
+	let (left, top) = (scissor.x as usize, scissor.y as usize);
+	let (width, height) = (scissor.width as usize, scissor.height as usize);
+
+	let inv_projection = uniforms.m_projection.inverse();
+
+	let clear = buffers.depth_clear();
+
+	// View space position of every covered pixel of the scissor.
+	let positions = scissor
+		.rows(stride as u32)
+		.enumerate()
+		.flat_map(|(y, row)| {
+			buffers.z_buffer[row]
+				.iter()
+				.enumerate()
+				.map(move |(x, &z)| (left + x, top + y, z))
+		})
+		.map(|(x, y, z)| {
+			(z != clear).then(|| view_position(x, y, z, &inv_projection, uniforms))
+		})
+		.collect::<Vec<_>>();
+
+	let kernel = kernel(settings.samples.clamp(1, MAX_SAMPLES));
+	let mut occlusion = vec![1.0; positions.len()];
+
+	for y in 0..height {
+		for x in 0..width {
+			let i = y * width + x;
+			let (sx, sy) = (left + x, top + y);
+
+			let Some(p) = positions[i] else {
+				continue;
+			};
+
+			let n = match buffers.g_buffer.get(sy * stride + sx) {
+				Some(Some(surface)) => (uniforms.m_view
+					* Vector4::from((surface.normal, 0.0)))
+				.xyz()
+				.normalize(),
+				_ => depth_normal(&positions, x, y, width, height, p),
+			};
+
+			// Per pixel rotation of the kernel around the normal, the blur
+			// removes the resulting noise pattern.
+			let angle = interleaved_gradient_noise(sx as f32, sy as f32) * TAU;
+			let (t, b) = rotated_basis(n, angle);
+
+			let mut occluded = 0.0;
+
+			for k in &kernel {
+				let s = p + (t * k.x + b * k.y + n * k.z) * settings.radius;
+
+				let Some((kx, ky)) = project(s, uniforms) else {
+					continue;
+				};
+
+				let Some(scene) = positions[(ky - top) * width + kx - left] else {
+					continue;
+				};
+
+				// Samples far outside the radius are different surfaces,
+				// fade them out instead of darkening silhouettes.
+				let range = smoothstep(settings.radius / (p.z - scene.z).abs());
+
+				if scene.z >= s.z + settings.bias {
+					occluded += range;
+				}
+			}
+
+			let ao = 1.0 - occluded / kernel.len() as f32;
+			occlusion[i] = ao.max(0.0).powf(settings.intensity);
+		}
+	}
+
+	if settings.blur > 0 {
+		let radius = settings.blur as isize;
+		blur(&mut occlusion, &positions, width, height, radius, true);
+		blur(&mut occlusion, &positions, width, height, radius, false);
+	}
+
+	for (row, ao) in scissor.rows(stride as u32).zip(occlusion.chunks(width)) {
+		buffers.ao[row].copy_from_slice(ao);
+	}
+}
+
+/// Hemisphere kernel oriented along +z, denser close to the origin.
+fn kernel(samples: u32) -> Vec<Vector3> {
+	// Golden angle spiral, evenly covering the hemisphere without a
+	// random number generator.
+	let golden_angle = TAU * (1.0 - 1.0 / 1.618_034);
+
+	(0..samples)
+		.map(|i| {
+			let t = (i as f32 + 0.5) / samples as f32;
+
+			let z = 1.0 - t;
+			let r = (1.0 - z * z).sqrt();
+			let phi = i as f32 * golden_angle;
+
+			let scale = 0.1 + 0.9 * t * t;
+
+			Vector3::new(r * phi.cos(), r * phi.sin(), z) * scale
+		})
+		.collect()
+}
+
+#[inline]
+fn view_position(
+	x: usize,
+	y: usize,
+	z: f32,
+	inv_projection: &Matrix4,
+	uniforms: &GlobalUniforms,
+) -> Vector3 {
+	let (ndc_x, ndc_y) = uniforms.screen.to_ndc(x as f32 + 0.5, y as f32 + 0.5);
+
+	let v = *inv_projection * Vector4::new(ndc_x, ndc_y, z, 1.0);
+
+	v.xyz() * (1.0 / v.w)
+}
+
+/// Projects a view space point to the pixel it covers.
+#[inline]
+fn project(p: Vector3, uniforms: &GlobalUniforms) -> Option<(usize, usize)> {
+	let clip = uniforms.m_projection * Vector4::from((p, 1.0));
+
+	if clip.w <= 0.0 {
+		return None;
+	}
+
+	let inv_w = 1.0 / clip.w;
+
+	let (x, y) = uniforms.screen.to_screen(clip.x * inv_w, clip.y * inv_w);
+
+	let in_bounds = x >= 0.0
+		&& y >= 0.0
+		&& uniforms.screen.scissor.contains(x as u32, y as u32);
+
+	in_bounds.then_some((x as usize, y as usize))
+}
+
+/// Reconstructs the view space normal from the neighbouring positions,
+/// picking on each axis the neighbour closest in depth to avoid smearing
+/// across silhouettes.
+fn depth_normal(
+	positions: &[Option<Vector3>],
+	x: usize,
+	y: usize,
+	width: usize,
+	height: usize,
+	p: Vector3,
+) -> Vector3 {
+	let at = |x: usize, y: usize| positions[y * width + x];
+
+	let closest = |a: Option<Vector3>, b: Option<Vector3>| match (a, b) {
+		(Some(a), Some(b)) if (a.z - p.z).abs() < (b.z - p.z).abs() => Some(p - a),
+		(_, Some(b)) => Some(b - p),
+		(Some(a), None) => Some(p - a),
+		(None, None) => None,
+	};
+
+	let left = (x > 0).then(|| at(x - 1, y)).flatten();
+	let right = (x + 1 < width).then(|| at(x + 1, y)).flatten();
+	let up = (y > 0).then(|| at(x, y - 1)).flatten();
+	let down = (y + 1 < height).then(|| at(x, y + 1)).flatten();
+
+	// Facing the camera when the neighbourhood is degenerate.
+	let facing = p * -1.0;
+
+	match (closest(left, right), closest(up, down)) {
+		(Some(dx), Some(dy)) => {
+			let n = dy.cross(&dx).normalize();
+
+			if n.dot(&facing) < 0.0 { n * -1.0 } else { n }
+		}
+		_ => facing.normalize(),
+	}
+}
+
+/// Tangent and bi-tangent around `n`, rotated by `angle`.
+#[inline]
+fn rotated_basis(n: Vector3, angle: f32) -> (Vector3, Vector3) {
+	let (t, b) = n.orthonormal_basis();
+
+	let (sin, cos) = angle.sin_cos();
+
+	(t * cos + b * sin, b * cos - t * sin)
+}
+
+/// Separable box blur skipping the neighbours which are too far apart in
+/// depth, so occlusion does not bleed over silhouettes.
+fn blur(
+	occlusion: &mut [f32],
+	positions: &[Option<Vector3>],
+	width: usize,
+	height: usize,
+	radius: isize,
+	horizontal: bool,
+) {
+	let source = occlusion.to_vec();
+
+	for y in 0..height {
+		for x in 0..width {
+			let i = y * width + x;
+
+			let Some(p) = positions[i] else {
+				continue;
+			};
+
+			let threshold = p.z.abs() * 0.05;
+
+			let mut sum = 0.0;
+			let mut count = 0.0;
+
+			for d in -radius..=radius {
+				let (nx, ny) = if horizontal {
+					(x as isize + d, y as isize)
+				} else {
+					(x as isize, y as isize + d)
+				};
+
+				if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+					continue;
+				}
+
+				let j = ny as usize * width + nx as usize;
+
+				if let Some(q) = positions[j]
+					&& (q.z - p.z).abs() <= threshold
+				{
+					sum += source[j];
+					count += 1.0;
+				}
+			}
+
+			occlusion[i] = sum / count;
+		}
+	}
+}
+
+#[inline(always)]
+fn interleaved_gradient_noise(x: f32, y: f32) -> f32 {
+	(52.982_918 * (0.067_110_56 * x + 0.005_837_15 * y).fract()).fract()
+}
+
+#[inline(always)]
+fn smoothstep(x: f32) -> f32 {
+	let x = x.clamp(0.0, 1.0);
+	x * x * (3.0 - 2.0 * x)
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::{kernel, rotated_basis},
+		pcore::math::Vector3,
+	};
+
+	#[test]
+	fn kernel_fills_the_hemisphere() {
+		let kernel = kernel(16);
+
+		assert_eq!(kernel.len(), 16);
+		assert!(kernel.iter().all(|k| k.z > 0.0 && k.magnitude() <= 1.0));
+
+		// Samples get farther from the origin, most of them stay close.
+		let lengths = kernel.iter().map(|k| k.magnitude()).collect::<Vec<_>>();
+		assert!(lengths.windows(2).all(|pair| pair[0] < pair[1]));
+		assert!(lengths[8] < 0.5);
+	}
+
+	#[test]
+	fn basis_is_orthonormal_around_the_normal() {
+		let normals = [
+			Vector3::new(0.0, 0.0, 1.0),
+			Vector3::new(1.0, 0.0, 0.0),
+			Vector3::new(0.3, -0.8, 0.5).normalize(),
+		];
+
+		for n in normals {
+			for angle in [0.0, 1.0, 4.0] {
+				let (t, b) = rotated_basis(n, angle);
+
+				assert!((t.magnitude() - 1.0).abs() < 1e-5);
+				assert!((b.magnitude() - 1.0).abs() < 1e-5);
+				assert!(t.dot(&n).abs() < 1e-5);
+				assert!(b.dot(&n).abs() < 1e-5);
+				assert!(t.dot(&b).abs() < 1e-5);
+			}
+		}
+	}
+}
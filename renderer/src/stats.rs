@@ -0,0 +1,163 @@
+use std::{
+	fmt,
+	ops::AddAssign,
+	time::{Duration, Instant},
+};
+
+/// Counters and stage timings collected while rendering a single frame.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct RenderStats {
+	pub objects_submitted: u32,
+	pub objects_culled: u32,
+
+	/// Unique vertices run through the vertex stage.
+	pub vertices_shaded: u32,
+
+	pub triangles_submitted: u32,
+	/// Triangles rejected because a vertex is behind the camera.
+	pub triangles_clipped: u32,
+	pub triangles_backfacing: u32,
+	pub triangles_rasterized: u32,
+
+	/// Covered fragments which reached the depth test.
+	pub fragments_tested: u64,
+	/// Fragments which passed the depth test, with the deferred pipeline
+	/// these are the G-buffer writes.
+	pub fragments_shaded: u64,
+	pub fragments_depth_rejected: u64,
+	pub fragments_stencil_rejected: u64,
+	/// Pixels lit by the deferred lighting pass.
+	pub fragments_lit: u64,
+
+	pub timings: StageTimings,
+}
+
+/// Wall time spent in each stage of the frame.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct StageTimings {
+	/// Depth-only pre-pass, zero when disabled.
+	pub pre_pass: Duration,
+	/// Vertex shading, clipping, projection and culling.
+	pub vertex: Duration,
+	/// Triangle setup, depth testing and fragment shading.
+	pub raster: Duration,
+	/// Screen-space ambient occlusion, zero when disabled.
+	pub ssao: Duration,
+	/// Deferred lighting pass, zero with the forward pipeline.
+	pub lighting: Duration,
+	/// Full screen passes run after all the draw calls.
+	pub post: Duration,
+	/// Copy of the frame buffer into the render target.
+	pub copy: Duration,
+}
+
+impl RenderStats {
+	pub fn reset(&mut self) {
+		*self = Self::default();
+	}
+}
+
+impl StageTimings {
+	pub fn total(&self) -> Duration {
+		self.pre_pass
+			+ self.vertex
+			+ self.raster
+			+ self.ssao
+			+ self.lighting
+			+ self.post
+			+ self.copy
+	}
+}
+
+/// Fragment counters kept on the stack inside the raster loop and merged
+/// into `RenderStats` once per triangle.
+#[derive(Default, Clone, Copy)]
+pub struct FragmentCounters {
+	pub tested: u64,
+	pub shaded: u64,
+	pub depth_rejected: u64,
+	pub stencil_rejected: u64,
+}
+
+impl AddAssign<FragmentCounters> for RenderStats {
+	#[inline(always)]
+	fn add_assign(&mut self, rhs: FragmentCounters) {
+		self.fragments_tested += rhs.tested;
+		self.fragments_shaded += rhs.shaded;
+		self.fragments_depth_rejected += rhs.depth_rejected;
+		self.fragments_stencil_rejected += rhs.stencil_rejected;
+	}
+}
+
+/// Measures consecutive spans of time, each `lap` returns the time since
+/// the previous one.
+pub struct Stopwatch {
+	last: Instant,
+}
+
+impl Stopwatch {
+	#[inline(always)]
+	pub fn start() -> Self {
+		Self {
+			last: Instant::now(),
+		}
+	}
+
+	#[inline(always)]
+	pub fn lap(&mut self) -> Duration {
+		let now = Instant::now();
+		let elapsed = now - self.last;
+		self.last = now;
+		elapsed
+	}
+}
+
+impl fmt::Display for RenderStats {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let ms = |d: Duration| d.as_secs_f32() * 1000.0;
+
+		write!(
+			f,
+			"Objects: {}/{} | Verts: {} | Tris: {}/{} (clip {}, back {}) | Frags: \
+			 {} shaded, {} rejected | ZP {:.2}ms VS {:.2}ms RS {:.2}ms AO {:.2}ms \
+			 LP {:.2}ms PP {:.2}ms CP {:.2}ms",
+			self.objects_submitted - self.objects_culled,
+			self.objects_submitted,
+			self.vertices_shaded,
+			self.triangles_rasterized,
+			self.triangles_submitted,
+			self.triangles_clipped,
+			self.triangles_backfacing,
+			self.fragments_shaded,
+			self.fragments_depth_rejected,
+			ms(self.timings.pre_pass),
+			ms(self.timings.vertex),
+			ms(self.timings.raster),
+			ms(self.timings.ssao),
+			ms(self.timings.lighting),
+			ms(self.timings.post),
+			ms(self.timings.copy),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {super::StageTimings, std::time::Duration};
+
+	#[test]
+	fn total_sums_every_stage() {
+		let ms = Duration::from_millis;
+		let timings = StageTimings {
+			pre_pass: ms(1),
+			vertex: ms(2),
+			raster: ms(4),
+			ssao: ms(8),
+			lighting: ms(16),
+			post: ms(32),
+			copy: ms(64),
+		};
+
+		assert_eq!(timings.total(), ms(127));
+	}
+}
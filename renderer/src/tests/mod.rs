@@ -1,3 +1,515 @@
+use {
+	crate::{
+		buffer::Buffers,
+		debug::DebugView,
+		deferred::Pipeline,
+		draw::DrawCall,
+		render::{Renderer, WinSize},
+		shaders::{
+			BlinnPhong, Flat, Matcap, Toon, ToonOutline, VS, VertexIn,
+			uniform::GlobalUniforms,
+		},
+		ssao::SsaoSettings,
+		stats::RenderStats,
+		view::{Rect, View},
+	},
+	pcore::{
+		color::Color,
+		geometry::{
+			Indices, Mesh, UV, generate_cube, generate_plane, generate_sphere,
+		},
+		math::{Vector3, Vector4},
+	},
+	pscene::{
+		assets::registry::AssetRegistry,
+		camera::Camera,
+		global::Scene,
+		light::Light,
+		material::{
+			CullMode, InkOutline, Material, ShaderModel, StencilCompare, StencilOp,
+			StencilState, ToonBands, ToonSettings,
+		},
+		model::Model,
+		object::{Instance, InstanceGroup, Object, ObjectRef},
+	},
+};
+
+const WIDTH: u32 = 96;
+const HEIGHT: u32 = 72;
+
+/// Sphere and cube resting on a plane, built without loading any asset.
+fn test_scene() -> Scene {
+	let mut scene = Scene {
+		camera: Camera::new(Vector3::new(0.0, 1.0, 6.0)),
+		light: Light::default(),
+		assets: AssetRegistry::default(),
+		objects: Vec::new(),
+		instances: Vec::new(),
+	};
+
+	let material = scene.assets.insert_material(Material::default());
+
+	let meshes = [
+		(generate_sphere(1.0, 16, 12), Vector3::new(-1.2, 0.0, 0.0)),
+		(generate_cube(1.5), Vector3::new(1.2, 0.0, 0.0)),
+		(generate_plane(10.0, 10.0), Vector3::new(0.0, -1.0, 0.0)),
+	];
+
+	for (mesh, position) in meshes {
+		let mesh = scene.assets.insert_mesh(mesh);
+		let mut object = Object::from_model(Model { mesh, material });
+		object.transform.position = position;
+		scene.objects.push(object);
+	}
+
+	scene
+}
+
+fn render(renderer: &mut Renderer, scene: &mut Scene) -> Vec<u8> {
+	let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+	renderer.render(scene, &mut frame[..]).unwrap();
+	frame
+}
+
+#[test]
+fn deferred_occlusion_is_applied_once() {
+	let mut scene = test_scene();
+
+	let mut renderer = Renderer::new(WIDTH, HEIGHT);
+	renderer.set_pipeline(Pipeline::Deferred);
+	renderer.set_ssao(Some(SsaoSettings::default()));
+
+	let without_pre_pass = render(&mut renderer, &mut scene);
+
+	renderer.set_depth_pre_pass(true);
+	let with_pre_pass = render(&mut renderer, &mut scene);
+
+	assert!(without_pre_pass == with_pre_pass);
+}
+
+#[test]
+fn pre_pass_reuses_the_transformed_vertices() {
+	let mut scene = test_scene();
+	let mut renderer = Renderer::new(WIDTH, HEIGHT);
+
+	let frame = render(&mut renderer, &mut scene);
+	let shaded = renderer.stats().vertices_shaded;
+
+	renderer.set_depth_pre_pass(true);
+	let with_pre_pass = render(&mut renderer, &mut scene);
+
+	// Only the vertices of the triangles left after culling need their
+	// varyings once the pre-pass has placed them all.
+	assert!(frame == with_pre_pass);
+	assert!(renderer.stats().vertices_shaded < shaded);
+}
+
+#[test]
+fn objects_out_of_view_are_culled() {
+	let mut scene = test_scene();
+	let mut renderer = Renderer::new(WIDTH, HEIGHT);
+
+	// The ink outline hull is drawn for the same object.
+	let outlined =
+		scene.assets.insert_material(toon_material(Some(InkOutline {
+			color: Color::BLACK,
+			width: 2.0,
+		})));
+	scene.objects[0].model.material = outlined;
+
+	let frame = render(&mut renderer, &mut scene);
+	assert_eq!(renderer.stats().objects_submitted, 3);
+	assert_eq!(renderer.stats().objects_culled, 0);
+
+	// Behind the camera.
+	scene.objects[0].transform.position = Vector3::new(0.0, 1.0, 20.0);
+	let without_sphere = render(&mut renderer, &mut scene);
+
+	assert_eq!(renderer.stats().objects_submitted, 3);
+	assert_eq!(renderer.stats().objects_culled, 1);
+	assert!(frame != without_sphere);
+}
+
+/// Indices of the objects in the order the sorted draw call hands them
+/// out.
+fn sorted_order(scene: &Scene) -> Vec<usize> {
+	let target = WinSize {
+		width: WIDTH,
+		height: HEIGHT,
+	};
+	let view = View::new(&scene.camera, Rect::full(target));
+
+	let mut draw_call = DrawCall::submit_draw_call(scene, &view, target);
+	draw_call.sort_front_to_back();
+
+	let mut order = Vec::new();
+	draw_call.execute(
+		&mut Buffers::new(WIDTH, HEIGHT),
+		&mut RenderStats::default(),
+		|_, _, uniforms, _| order.push(uniforms.primitive.object),
+	);
+
+	order
+}
+
+#[test]
+fn only_opaque_objects_are_sorted() {
+	let mut scene = test_scene();
+
+	scene.objects[0].transform.position.z = -3.0;
+	scene.objects[1].transform.position.z = 2.0;
+
+	// The plane and a sphere closer than everything else don't write depth.
+	let overlay = scene.assets.insert_material(Material {
+		depth_write: false,
+		..Default::default()
+	});
+	scene.objects[2].model.material = overlay;
+
+	let mesh = scene.objects[0].model.mesh;
+	let mut object = Object::from_model(Model {
+		mesh,
+		material: overlay,
+	});
+	object.transform.position = Vector3::new(0.0, 0.0, 3.0);
+	scene.objects.push(object);
+
+	assert_eq!(sorted_order(&scene), [1, 0, 2, 3]);
+}
+
+#[test]
+fn stencil_mask_is_drawn_before_the_geometry_it_masks() {
+	let mut scene = test_scene();
+
+	// The sphere marks the stencil behind the cube, which is only drawn
+	// over it.
+	let mask = scene.assets.insert_material(Material {
+		stencil: Some(StencilState {
+			reference: 1,
+			pass: StencilOp::Replace,
+			..Default::default()
+		}),
+		..Default::default()
+	});
+	let masked = scene.assets.insert_material(Material {
+		stencil: Some(StencilState {
+			reference: 1,
+			compare: StencilCompare::Equal,
+			..Default::default()
+		}),
+		..Default::default()
+	});
+
+	scene.objects[0].model.material = mask;
+	scene.objects[0].transform.position.z = -2.0;
+	scene.objects[1].model.material = masked;
+	scene.objects[1].transform.position = Vector3::new(-0.6, 0.0, 1.0);
+
+	let mut renderer = Renderer::new(WIDTH, HEIGHT);
+	let frame = render(&mut renderer, &mut scene);
+
+	// Behind the camera.
+	scene.objects[1].transform.position.z = 20.0;
+	let without_cube = render(&mut renderer, &mut scene);
+
+	// Unmasked, the cube covers more than the sphere.
+	scene.objects[1].transform.position.z = 1.0;
+	scene.objects[1].model.material = scene.objects[2].model.material;
+	let unmasked = render(&mut renderer, &mut scene);
+
+	assert!(frame != without_cube);
+	assert!(frame != unmasked);
+}
+
+#[test]
+fn flat_shading_uses_the_face_normal() {
+	let mut scene = test_scene();
+	scene.objects.clear();
+	scene.camera = Camera::new(Vector3::new(0.0, 0.0, 5.0));
+
+	// A triangle facing the camera whose vertex normals all point right.
+	let vertices = vec![
+		Vector3::new(-1.0, -1.0, 0.0),
+		Vector3::new(1.0, -1.0, 0.0),
+		Vector3::new(0.0, 1.0, 0.0),
+	];
+	let indices = Indices {
+		v: vec![0, 1, 2],
+		n: vec![0, 0, 0],
+		uv: vec![0, 0, 0],
+	};
+	let uv = vec![UV::new(0.0, 0.0)];
+	let normals = vec![Vector3::new(1.0, 0.0, 0.0)];
+
+	let mesh = scene
+		.assets
+		.insert_mesh(Mesh::new(vertices, uv, indices, normals));
+
+	let mut material = Material::default();
+	material.set_shader_model(ShaderModel::Flat);
+	material.set_cull_mode(CullMode::None);
+	let material = scene.assets.insert_material(material);
+
+	scene
+		.objects
+		.push(Object::from_model(Model { mesh, material }));
+
+	let mut renderer = Renderer::new(WIDTH, HEIGHT);
+	renderer.set_debug_view(DebugView::Normals);
+
+	let frame = render(&mut renderer, &mut scene);
+	let center = ((HEIGHT / 2 * WIDTH + WIDTH / 2) * 4) as usize;
+	let [r, _, b, _] = [0, 1, 2, 3].map(|c| frame[center + c]);
+
+	// The normal points along z towards the camera, not along x.
+	assert!(b > r, "red {r} blue {b}");
+}
+
+/// Pixels of a sphere drawn with `material`, the background left out.
+fn sphere_pixels(material: Material) -> Vec<[u8; 3]> {
+	stacked_sphere_pixels([material])
+}
+
+/// Pixels of the same sphere drawn once per material, one over the other.
+fn stacked_sphere_pixels(
+	materials: impl IntoIterator<Item = Material>,
+) -> Vec<[u8; 3]> {
+	let mut scene = test_scene();
+	scene.objects.clear();
+	scene.camera = Camera::new(Vector3::new(0.0, 0.0, 4.0));
+
+	let mut renderer = Renderer::new(WIDTH, HEIGHT);
+	let background = render(&mut renderer, &mut scene);
+
+	let mesh = scene.assets.insert_mesh(generate_sphere(1.0, 24, 16));
+
+	for material in materials {
+		let material = scene.assets.insert_material(material);
+		scene
+			.objects
+			.push(Object::from_model(Model { mesh, material }));
+	}
+
+	let frame = render(&mut renderer, &mut scene);
+
+	frame
+		.chunks_exact(4)
+		.zip(background.chunks_exact(4))
+		.filter(|(pixel, background)| pixel != background)
+		.map(|(pixel, _)| [pixel[0], pixel[1], pixel[2]])
+		.collect()
+}
+
+fn distinct(pixels: &[[u8; 3]]) -> usize {
+	let mut pixels = pixels.to_vec();
+	pixels.sort();
+	pixels.dedup();
+	pixels.len()
+}
+
+fn toon_material(outline: Option<InkOutline>) -> Material {
+	let mut material = Material::default();
+	material.set_shader_model(ShaderModel::Toon);
+	material.set_toon(ToonSettings {
+		bands: ToonBands::new(&[(0.0, 0.3), (0.5, 1.0)]),
+		// Out of reach, the highlight would add a shade.
+		specular_threshold: 1.0,
+		rim: None,
+		outline,
+	});
+
+	material
+}
+
+#[test]
+fn toon_bands_quantize_the_lighting() {
+	// Unlit side, then the two bands.
+	assert_eq!(distinct(&sphere_pixels(toon_material(None))), 3);
+
+	assert!(distinct(&sphere_pixels(Material::default())) > 3);
+}
+
+#[test]
+fn toon_outline_inks_the_silhouette() {
+	let ink = [255, 0, 255];
+	let outline = InkOutline {
+		color: Color::new_rgb(1.0, 0.0, 1.0),
+		width: 2.0,
+	};
+
+	let plain = sphere_pixels(toon_material(None));
+	let outlined = sphere_pixels(toon_material(Some(outline)));
+
+	assert!(!plain.contains(&ink));
+	assert!(outlined.contains(&ink));
+
+	// The hull grows the silhouette rather than covering the sphere.
+	assert!(outlined.len() > plain.len());
+	assert!(outlined.iter().filter(|&&p| p == ink).count() < plain.len() / 2);
+}
+
+#[test]
+fn outline_width_is_part_of_the_cached_positions() {
+	let outline = |width| {
+		toon_material(Some(InkOutline {
+			color: Color::new_rgb(1.0, 0.0, 1.0),
+			width,
+		}))
+	};
+
+	// Same mesh and transform, the wider hull must not reuse the
+	// positions extruded for the thin one.
+	let wide = sphere_pixels(outline(4.0));
+	let both = stacked_sphere_pixels([outline(1.0), outline(4.0)]);
+
+	assert!(wide.len() > sphere_pixels(outline(1.0)).len());
+	assert_eq!(both.len(), wide.len());
+}
+
+#[test]
+fn shader_models_dispatch_to_their_shader() {
+	let models = [
+		ShaderModel::Flat,
+		ShaderModel::BlinnPhong,
+		ShaderModel::Toon,
+		ShaderModel::Matcap,
+	];
+
+	let frames = models.map(|model| {
+		let mut material = Material::default();
+		material.set_shader_model(model);
+		sphere_pixels(material)
+	});
+
+	for (i, a) in frames.iter().enumerate() {
+		for b in &frames[i + 1..] {
+			assert!(a != b);
+		}
+	}
+}
+
+#[test]
+fn instances_render_like_objects() {
+	let mut objects = test_scene();
+	let mut instanced = test_scene();
+
+	// The sphere and the cube, with a copy of each behind them.
+	for object in instanced.objects.drain(..2) {
+		let Model { mesh, material } = object.model;
+		let mut group = InstanceGroup::new(Model { mesh, material });
+
+		let mut copy = object.transform;
+		copy.position.z -= 1.5;
+
+		group.push(Instance::new(object.transform));
+		group.push(Instance::new(copy));
+
+		let mut object = Object::from_model(Model { mesh, material });
+		object.transform = copy;
+		objects.objects.push(object);
+
+		instanced.instances.push(group);
+	}
+
+	let mut renderer = Renderer::new(WIDTH, HEIGHT);
+	let frame = render(&mut renderer, &mut objects);
+	let with_instances = render(&mut renderer, &mut instanced);
+
+	assert!(frame == with_instances);
+}
+
+#[test]
+fn pick_finds_the_surface_under_the_pixel() {
+	let mut scene = test_scene();
+
+	// A copy of the cube, numbered after the objects.
+	let cube = &scene.objects[1];
+	let Model { mesh, material } = cube.model;
+	let mut group = InstanceGroup::new(Model { mesh, material });
+	let mut transform = cube.transform;
+	transform.position.y += 2.0;
+	group.push(Instance::new(transform));
+	scene.instances.push(group);
+
+	let mut renderer = Renderer::new(WIDTH, HEIGHT);
+	render(&mut renderer, &mut scene);
+
+	// Ids are only tracked once picking is enabled.
+	assert!(renderer.pick(WIDTH / 2, HEIGHT / 2).is_none());
+
+	renderer.set_picking(true);
+	render(&mut renderer, &mut scene);
+
+	let target = WinSize {
+		width: WIDTH,
+		height: HEIGHT,
+	};
+	let view = View::new(&scene.camera, Rect::full(target));
+	let uniforms = GlobalUniforms::new(target, &view, &scene.light);
+
+	let pixel = |p: Vector3| {
+		let clip = uniforms.m_view_projection * Vector4::from((p, 1.0));
+		let (x, y) = uniforms.screen.to_screen(clip.x / clip.w, clip.y / clip.w);
+		(x as u32, y as u32)
+	};
+
+	let sphere = scene.objects[0].transform.position;
+	let (x, y) = pixel(sphere);
+	let pick = renderer.pick(x, y).unwrap();
+
+	assert_eq!(pick.object, 0);
+	// On the front of the sphere, up to the tessellation.
+	assert!(((pick.position - sphere).magnitude() - 1.0).abs() < 0.1);
+	assert!(pick.position.z > sphere.z);
+	assert!(pick.depth > 0.0);
+
+	let (x, y) = pixel(transform.position);
+	assert_eq!(renderer.pick(x, y).unwrap().object, scene.objects.len());
+
+	// Sky above the objects.
+	assert!(renderer.pick(WIDTH / 2, 0).is_none());
+}
+
+#[test]
+fn position_path_matches_the_vertex_stage() {
+	let mut scene = test_scene();
+	let outline = InkOutline {
+		color: Color::BLACK,
+		width: 2.0,
+	};
+	scene.objects[0].model.material =
+		scene.assets.insert_material(toon_material(Some(outline)));
+
+	let object = scene.objects[0].resolve(&scene.assets);
+	let target = WinSize {
+		width: WIDTH,
+		height: HEIGHT,
+	};
+	let view = View::new(&scene.camera, Rect::full(target));
+	let uniforms = GlobalUniforms::new(target, &view, &scene.light);
+
+	fn check<S: VS>(
+		shader: &S,
+		object: ObjectRef<'_>,
+		uniforms: &GlobalUniforms,
+	) {
+		for &attributes in object.model.mesh.vertex_buffer() {
+			let input = VertexIn { attributes };
+			let a = shader.shade_vertex(input, object, uniforms).clip;
+			let b = shader.shade_position(input, object, uniforms);
+
+			// Bit for bit, the pre-pass depth is tested for equality.
+			assert_eq!([a.x, a.y, a.z, a.w], [b.x, b.y, b.z, b.w]);
+		}
+	}
+
+	check(&BlinnPhong, object, &uniforms);
+	check(&Flat, object, &uniforms);
+	check(&Toon, object, &uniforms);
+	check(&Matcap, object, &uniforms);
+	check(&ToonOutline, object, &uniforms);
+}
+
 #[test]
 pub fn point_inside_triangle() {
 	use pcore::{geometry::edge_function, math::Vector2};
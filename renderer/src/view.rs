@@ -0,0 +1,136 @@
+use {crate::render::WinSize, pscene::camera::Camera, std::ops::Range};
+
+/// Rectangle of pixels in the render target, `x` and `y` being its top
+/// left corner.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+	pub x: u32,
+	pub y: u32,
+	pub width: u32,
+	pub height: u32,
+}
+
+impl Rect {
+	pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+		Self {
+			x,
+			y,
+			width,
+			height,
+		}
+	}
+
+	/// Rectangle covering the whole target.
+	pub fn full(size: WinSize) -> Self {
+		Self::new(0, 0, size.width, size.height)
+	}
+
+	#[inline]
+	pub fn right(&self) -> u32 {
+		self.x + self.width
+	}
+
+	#[inline]
+	pub fn bottom(&self) -> u32 {
+		self.y + self.height
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.width == 0 || self.height == 0
+	}
+
+	/// Width over height, 1 for an empty rectangle so the projection stays
+	/// finite.
+	pub fn aspect(&self) -> f32 {
+		if self.is_empty() {
+			return 1.0;
+		}
+
+		self.width as f32 / self.height as f32
+	}
+
+	#[inline]
+	pub fn contains(&self, x: u32, y: u32) -> bool {
+		x >= self.x && y >= self.y && x < self.right() && y < self.bottom()
+	}
+
+	/// Overlapping area of both rectangles, empty when they are disjoint.
+	pub fn intersect(&self, other: &Rect) -> Rect {
+		let x = self.x.max(other.x);
+		let y = self.y.max(other.y);
+		let right = self.right().min(other.right());
+		let bottom = self.bottom().min(other.bottom());
+
+		Rect::new(x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+	}
+
+	/// Buffer offsets of every row for a target `stride` pixels wide.
+	pub fn rows(&self, stride: u32) -> impl Iterator<Item = Range<usize>> {
+		let (x, width) = (self.x as usize, self.width as usize);
+		let stride = stride as usize;
+
+		(self.y as usize..self.bottom() as usize).map(move |y| {
+			let start = y * stride + x;
+			start..start + width
+		})
+	}
+}
+
+/// Camera rendered into a part of the target, e.g. one pane of a split
+/// screen or a picture-in-picture mirror.
+#[derive(Clone, Copy)]
+pub struct View<'a> {
+	pub camera: &'a Camera,
+	/// Area the normalized device coordinates are mapped to, its aspect
+	/// ratio is used for the projection.
+	pub viewport: Rect,
+	/// Further restricts the written pixels, the viewport when unset.
+	pub scissor: Option<Rect>,
+}
+
+impl<'a> View<'a> {
+	pub fn new(camera: &'a Camera, viewport: Rect) -> Self {
+		Self {
+			camera,
+			viewport,
+			scissor: None,
+		}
+	}
+
+	pub fn with_scissor(mut self, scissor: Rect) -> Self {
+		self.scissor = Some(scissor);
+		self
+	}
+
+	/// Pixels written by the view, clipped to the viewport and the target.
+	pub fn clip_rect(&self, target: WinSize) -> Rect {
+		let rect = self.viewport.intersect(&Rect::full(target));
+
+		match &self.scissor {
+			Some(scissor) => rect.intersect(scissor),
+			None => rect,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Rect;
+
+	#[test]
+	fn intersect_clips_to_the_overlap() {
+		let a = Rect::new(0, 0, 10, 10);
+
+		assert_eq!(a.intersect(&Rect::new(5, 2, 10, 4)), Rect::new(5, 2, 5, 4));
+		assert_eq!(a.intersect(&Rect::new(2, 2, 3, 3)), Rect::new(2, 2, 3, 3));
+		assert!(a.intersect(&Rect::new(10, 0, 5, 5)).is_empty());
+		assert!(a.intersect(&Rect::new(20, 20, 5, 5)).is_empty());
+	}
+
+	#[test]
+	fn aspect_of_empty_rect_is_finite() {
+		assert_eq!(Rect::new(0, 0, 20, 10).aspect(), 2.0);
+		assert_eq!(Rect::new(0, 0, 20, 0).aspect(), 1.0);
+		assert_eq!(Rect::default().aspect(), 1.0);
+	}
+}
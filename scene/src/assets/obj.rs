@@ -1,5 +1,6 @@
 use {
 	pcore::{
+		color::Color,
 		error::PResult,
 		geometry::{Indices, Mesh},
 		math::{Vector2, Vector3},
@@ -20,19 +21,23 @@ where
 
 	let mut uv = vec![];
 	let mut vertices = vec![];
+	let mut colors = vec![];
 	let mut normals = vec![];
 	let mut indices = Indices::default();
 
 	for line in reader.lines() {
 		let line = line?;
-		let parts = line.split_whitespace().collect::<Vec<_>>();
+
+		// Trailing comments would otherwise be read as vertex colors.
+		let statement = line.split('#').next().unwrap_or_default();
+		let parts = statement.split_whitespace().collect::<Vec<_>>();
 
 		if parts.is_empty() {
 			continue;
 		}
 
 		match parts[0] {
-			"v" => parse_vector3(&parts[1..], &mut vertices)?,
+			"v" => parse_vertex(&parts[1..], &mut vertices, &mut colors)?,
 			"vn" => parse_vector3(&parts[1..], &mut normals)?,
 			"vt" => parse_vector2(&parts[1..], &mut uv)?,
 			"f" => parse_indices(&parts[1..], &mut indices)?,
@@ -43,9 +48,42 @@ where
 	}
 
 	let mesh = Mesh::new(vertices, uv, indices, normals);
+
+	// Colors are only kept when at least one vertex is colored.
+	if colors.iter().any(Option::is_some) {
+		let colors = colors
+			.into_iter()
+			.map(|c| c.unwrap_or(Color::WHITE))
+			.collect();
+
+		return Ok(mesh.with_colors(colors));
+	}
+
 	Ok(mesh)
 }
 
+/// Parses a position with its optional `r g b` color extension.
+pub fn parse_vertex(
+	parts: &[&str],
+	vertices: &mut Vec<Vector3>,
+	colors: &mut Vec<Option<Color>>,
+) -> PResult<()> {
+	parse_vector3(parts, vertices)?;
+
+	let color = if parts.len() >= 6 {
+		Some(Color::new_rgb(
+			parts[3].parse()?,
+			parts[4].parse()?,
+			parts[5].parse()?,
+		))
+	} else {
+		None
+	};
+
+	colors.push(color);
+	Ok(())
+}
+
 pub fn parse_vector3(
 	parts: &[&str],
 	vertices: &mut Vec<Vector3>,
@@ -101,7 +139,7 @@ pub fn parse_indices(parts: &[&str], indices: &mut Indices) -> PResult<()> {
 
 #[cfg(test)]
 mod tests {
-	use crate::assets::obj::load_obj;
+	use crate::assets::obj::{load_obj, parse_vertex};
 
 	#[test]
 	fn load_example_cube() {
@@ -117,4 +155,22 @@ mod tests {
 		assert!(mesh.normals.len() == 6);
 		assert!(mesh.vertices.len() == 8);
 	}
+
+	#[test]
+	fn parse_colored_vertex() {
+		let mut vertices = vec![];
+		let mut colors = vec![];
+
+		parse_vertex(
+			&["1", "2", "3", "1", "0.5", "0"],
+			&mut vertices,
+			&mut colors,
+		)
+		.unwrap();
+		parse_vertex(&["4", "5", "6"], &mut vertices, &mut colors).unwrap();
+
+		assert_eq!(vertices.len(), 2);
+		assert_eq!(colors[0].unwrap().to_rgba8(), [255, 127, 0, 255]);
+		assert!(colors[1].is_none());
+	}
 }
@@ -1,28 +1,42 @@
 use {
 	crate::{
 		material::{Material, MaterialRef},
-		texture::{AlbedoMap as Albedo, NormalMap},
+		texture::{
+			AlbedoMap as Albedo, EmissiveMap, HeightMap, MatcapMap, NormalMap,
+			OcclusionMap, SpecularMap,
+		},
 	},
 	pcore::geometry::Mesh,
 	std::marker::PhantomData,
 };
 
+/// Handle of an asset of type `T` in the store of kind `K`. Stores holding
+/// the same asset type for different purposes get their own kind, so the
+/// handle of one doesn't resolve against another.
+///
+/// ```compile_fail
+/// use pscene::assets::registry::{AssetRegistry, HeightHandle};
+///
+/// let registry = AssetRegistry::new();
+/// let height = HeightHandle::new(0, 0);
+/// registry.get_specular(&height);
+/// ```
 #[derive(Default)]
-pub struct AssetHandle<T> {
+pub struct AssetHandle<T, K = T> {
 	index: u32,
 	generation: u32,
-	_marker: PhantomData<T>,
+	_marker: PhantomData<(T, K)>,
 }
 
-impl<T> Clone for AssetHandle<T> {
+impl<T, K> Clone for AssetHandle<T, K> {
 	fn clone(&self) -> Self {
 		*self
 	}
 }
 
-impl<T> Copy for AssetHandle<T> {}
+impl<T, K> Copy for AssetHandle<T, K> {}
 
-impl<T> AssetHandle<T> {
+impl<T, K> AssetHandle<T, K> {
 	pub fn new(index: u32, generation: u32) -> Self {
 		Self {
 			index,
@@ -35,29 +49,54 @@ impl<T> AssetHandle<T> {
 pub type MeshHandle = AssetHandle<Mesh>;
 pub type AlbedoHandle = AssetHandle<Albedo>;
 pub type NormalHandle = AssetHandle<NormalMap>;
+pub type HeightHandle = AssetHandle<HeightMap, kind::Height>;
+pub type EmissiveHandle = AssetHandle<EmissiveMap, kind::Emissive>;
+pub type SpecularHandle = AssetHandle<SpecularMap, kind::Specular>;
+pub type OcclusionHandle = AssetHandle<OcclusionMap, kind::Occlusion>;
+pub type MatcapHandle = AssetHandle<MatcapMap, kind::Matcap>;
 pub type MaterialHandle = AssetHandle<Material>;
 
+/// Kinds of the stores sharing their asset type with another one.
+pub mod kind {
+	#[derive(Default)]
+	pub struct Height;
+
+	#[derive(Default)]
+	pub struct Emissive;
+
+	#[derive(Default)]
+	pub struct Specular;
+
+	#[derive(Default)]
+	pub struct Occlusion;
+
+	#[derive(Default)]
+	pub struct Matcap;
+}
+
 pub struct AssetSlot<T> {
 	generation: u32,
 	asset: Option<T>,
 }
 
-pub struct AssetStore<T> {
+pub struct AssetStore<T, K = T> {
 	list: Vec<AssetSlot<T>>,
 	free: Vec<u32>,
+	_kind: PhantomData<K>,
 }
 
-impl<T> Default for AssetStore<T> {
+impl<T, K> Default for AssetStore<T, K> {
 	fn default() -> Self {
 		Self {
 			list: Vec::new(),
 			free: Vec::new(),
+			_kind: PhantomData,
 		}
 	}
 }
 
-impl<T> AssetStore<T> {
-	pub fn insert(&mut self, asset: T) -> AssetHandle<T> {
+impl<T, K> AssetStore<T, K> {
+	pub fn insert(&mut self, asset: T) -> AssetHandle<T, K> {
 		let (index, generation) = match self.free.pop() {
 			Some(index) => {
 				let slot = self.list.get_mut(index as usize).unwrap();
@@ -81,7 +120,7 @@ impl<T> AssetStore<T> {
 		AssetHandle::new(index, generation)
 	}
 
-	pub fn get(&self, handle: &AssetHandle<T>) -> Option<&T> {
+	pub fn get(&self, handle: &AssetHandle<T, K>) -> Option<&T> {
 		let slot = self.list.get(handle.index as usize)?;
 		if slot.generation != handle.generation {
 			return None;
@@ -89,7 +128,15 @@ impl<T> AssetStore<T> {
 		slot.asset.as_ref()
 	}
 
-	pub fn remove(&mut self, handle: &AssetHandle<T>) -> bool {
+	pub fn get_mut(&mut self, handle: &AssetHandle<T, K>) -> Option<&mut T> {
+		let slot = self.list.get_mut(handle.index as usize)?;
+		if slot.generation != handle.generation {
+			return None;
+		}
+		slot.asset.as_mut()
+	}
+
+	pub fn remove(&mut self, handle: &AssetHandle<T, K>) -> bool {
 		if let Some(slot) = self.list.get_mut(handle.index as usize)
 			&& slot.generation == handle.generation
 		{
@@ -113,6 +160,10 @@ macro_rules! impl_asset_type {
 						self.[<$name s>].get(handle)
 				}
 
+				pub fn [<get_ $name _mut>](&mut self, handle: &$handle) -> Option<&mut $type> {
+						self.[<$name s>].get_mut(handle)
+				}
+
 				pub fn [<remove_ $name>](&mut self, handle: &$handle) -> bool {
 						self.[<$name s>].remove(handle)
 				}
@@ -125,6 +176,11 @@ pub struct AssetRegistry {
 	meshs: AssetStore<Mesh>,
 	albedos: AssetStore<Albedo>,
 	normals: AssetStore<NormalMap>,
+	heights: AssetStore<HeightMap, kind::Height>,
+	emissives: AssetStore<EmissiveMap, kind::Emissive>,
+	speculars: AssetStore<SpecularMap, kind::Specular>,
+	occlusions: AssetStore<OcclusionMap, kind::Occlusion>,
+	matcaps: AssetStore<MatcapMap, kind::Matcap>,
 	materials: AssetStore<Material>,
 }
 
@@ -135,6 +191,16 @@ impl AssetRegistry {
 
 	impl_asset_type!(normal, NormalMap, NormalHandle);
 
+	impl_asset_type!(height, HeightMap, HeightHandle);
+
+	impl_asset_type!(emissive, EmissiveMap, EmissiveHandle);
+
+	impl_asset_type!(specular, SpecularMap, SpecularHandle);
+
+	impl_asset_type!(occlusion, OcclusionMap, OcclusionHandle);
+
+	impl_asset_type!(matcap, MatcapMap, MatcapHandle);
+
 	impl_asset_type!(material, Material, MaterialHandle);
 
 	pub fn new() -> Self {
@@ -3,14 +3,23 @@ use pcore::math::{Matrix4, Vector3};
 //     input::{Controller, Keys},
 // };
 
-const UP: usize = 0;
-const RIGHT: usize = 1;
-const FORWARD: usize = 2;
+/// Indices of the camera axes in `Camera::basis`.
+pub const UP: usize = 0;
+pub const RIGHT: usize = 1;
+pub const FORWARD: usize = 2;
 
 pub struct Camera {
 	pub fov: f32,
 	pub near: f32,
 	pub far: f32,
+	/// Distance to the plane in perfect focus.
+	pub focus_distance: f32,
+	/// Focal length divided by the aperture diameter, lower values blur
+	/// more away from the focus plane.
+	pub f_stop: f32,
+	/// Height of the sensor in world units, 0.024 is a full frame camera in
+	/// a scene measured in meters. Sets the focal length with the `fov`.
+	pub sensor_height: f32,
 	pub position: Vector3,
 	pub speed: f32,
 	pub sensitivity: f32,
@@ -25,6 +34,9 @@ impl Camera {
 			fov: 60.0f32.to_radians(),
 			near: 0.1,
 			far: 100.0,
+			focus_distance: 5.0,
+			f_stop: 2.8,
+			sensor_height: 0.024,
 			position,
 			speed: 0.05,
 			sensitivity: 0.1,
@@ -74,6 +86,27 @@ impl Camera {
 		self.basis[UP] = self.basis[RIGHT].cross(&self.basis[FORWARD]).normalize();
 	}
 
+	/// Focal length of the thin lens covering the sensor with the field of
+	/// view, in world units.
+	pub fn focal_length(&self) -> f32 {
+		self.sensor_height / (2.0 * (self.fov * 0.5).tan())
+	}
+
+	/// Diameter of the circle of confusion of a point `depth` units in
+	/// front of the camera, relative to the sensor height. Negative in front
+	/// of the focus plane, zero on it.
+	pub fn circle_of_confusion(&self, depth: f32) -> f32 {
+		let f = self.focal_length();
+		let focus = self.focus_distance.max(f + f32::EPSILON);
+		let aperture = f / self.f_stop.max(f32::EPSILON);
+
+		// Thin lens, A * f * (d - s) / (d * (s - f)) written so a point at
+		// infinity reaches the limit.
+		let coc = aperture * f * (1.0 - focus / depth) / (focus - f);
+
+		coc / self.sensor_height
+	}
+
 	pub fn get_view_matrix(&self) -> Matrix4 {
 		let r = self.basis[RIGHT];
 		let u = self.basis[UP];
@@ -93,4 +126,39 @@ impl Camera {
 	pub fn get_projection_matrix(&self, aspect: f32) -> Matrix4 {
 		Matrix4::perspective_matrix(self.fov, aspect, self.near, self.far)
 	}
+
+	/// Reverse-Z projection, `far` is ignored as the far plane is at
+	/// infinity.
+	pub fn get_reverse_z_projection_matrix(&self, aspect: f32) -> Matrix4 {
+		Matrix4::reverse_z_perspective_matrix(self.fov, aspect, self.near)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {super::Camera, pcore::math::Vector3};
+
+	#[test]
+	fn circle_of_confusion_grows_away_from_focus() {
+		let camera = Camera::new(Vector3::ZERO);
+		let coc = |depth: f32| camera.circle_of_confusion(depth);
+
+		assert!(coc(camera.focus_distance).abs() < 1e-6);
+		assert!(coc(2.0) < 0.0 && coc(1.0) < coc(2.0));
+		assert!(coc(10.0) > 0.0 && coc(10.0) < coc(100.0));
+
+		// Far points approach the value at infinity.
+		let f = camera.focal_length();
+		let limit = f / camera.f_stop * f / (camera.focus_distance - f);
+		assert!((coc(1e9) - limit / camera.sensor_height).abs() < 1e-5);
+	}
+
+	#[test]
+	fn wider_aperture_blurs_more() {
+		let mut camera = Camera::new(Vector3::ZERO);
+		let narrow = camera.circle_of_confusion(20.0);
+
+		camera.f_stop = 1.4;
+		assert!(camera.circle_of_confusion(20.0) > narrow);
+	}
 }
@@ -3,7 +3,7 @@ use {
 		assets::registry::AssetRegistry,
 		camera::Camera,
 		light::Light,
-		object::Object,
+		object::{InstanceGroup, Object},
 		texture::{AlbedoMap as Albedo, NormalMap, Wrap},
 	},
 	pcore::math::Vector3,
@@ -12,6 +12,8 @@ use {
 pub struct Scene {
 	pub assets: AssetRegistry,
 	pub objects: Vec<Object>,
+	/// Instanced models, drawn after `objects`.
+	pub instances: Vec<InstanceGroup>,
 	pub camera: Camera,
 	pub light: Light,
 }
@@ -23,6 +25,7 @@ impl Default for Scene {
 			light: Light::default(),
 			assets: AssetRegistry::default(),
 			objects: Vec::new(),
+			instances: Vec::new(),
 		};
 
 		// let camera = Camera::new(Vector3::new(0.0, 0.0, 5.0));
@@ -1,21 +1,320 @@
 use pcore::color::Color;
 
 use crate::{
-	assets::registry::{AlbedoHandle, AssetRegistry, NormalHandle},
+	assets::registry::{
+		AlbedoHandle, AssetRegistry, EmissiveHandle, HeightHandle, MatcapHandle,
+		NormalHandle, OcclusionHandle, SpecularHandle,
+	},
 	// color::Color,
-	texture::{AlbedoMap as Albedo, NormalMap},
+	texture::{
+		AlbedoMap as Albedo, EmissiveMap, HeightMap, MatcapMap, NormalMap,
+		OcclusionMap, SpecularMap,
+	},
 };
 
-#[derive(Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ShaderModel {
 	Flat,
+	#[default]
 	BlinnPhong,
+	/// Cel shading, the lighting is snapped to a few flat bands.
+	Toon,
+	/// Unlit, the color is read from a matcap by the view space normal.
+	/// Meant for inspecting the shape of meshes.
+	Matcap,
+}
+
+/// Upper bound of the diffuse bands of the toon shading.
+pub const MAX_TOON_BANDS: usize = 8;
+
+/// Parameters of the `ShaderModel::Toon` shading.
+#[derive(Debug, Clone, Copy)]
+pub struct ToonSettings {
+	/// Shades of the lit side the diffuse term is snapped to. Ignored when
+	/// a ramp texture is bound.
+	pub bands: ToonBands,
+	/// Specular factor above which the highlight is drawn at full
+	/// strength, 1 hides it.
+	pub specular_threshold: f32,
+	/// Light catching the silhouette, disabled when not set.
+	pub rim: Option<RimLight>,
+	/// Ink line drawn around the object, disabled when not set.
+	pub outline: Option<InkOutline>,
+}
+
+impl Default for ToonSettings {
+	fn default() -> Self {
+		Self {
+			bands: ToonBands::even(3),
+			specular_threshold: 0.5,
+			rim: None,
+			outline: None,
+		}
+	}
+}
+
+/// Diffuse bands of the toon shading. The lit side is split at the
+/// thresholds of the bands and every band is lit by a single shade.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToonBands {
+	len: usize,
+	/// Cosine between the normal and the light above which every band
+	/// starts, increasing.
+	thresholds: [f32; MAX_TOON_BANDS],
+	/// Diffuse factor of every band.
+	shades: [f32; MAX_TOON_BANDS],
+}
+
+impl ToonBands {
+	/// Bands from `(threshold, shade)` pairs, sorted by threshold and
+	/// limited to `MAX_TOON_BANDS`. Surfaces below the lowest threshold are
+	/// unlit.
+	pub fn new(bands: &[(f32, f32)]) -> Self {
+		let mut sorted = [(0.0, 0.0); MAX_TOON_BANDS];
+		let len = bands.len().min(MAX_TOON_BANDS);
+
+		sorted[..len].copy_from_slice(&bands[..len]);
+		sorted[..len].sort_by(|a, b| a.0.total_cmp(&b.0));
+
+		Self {
+			len,
+			thresholds: sorted.map(|(threshold, _)| threshold),
+			shades: sorted.map(|(_, shade)| shade),
+		}
+	}
+
+	/// `count` evenly spaced bands over the lit side, each lit like its
+	/// brightest point.
+	pub fn even(count: u32) -> Self {
+		let count = (count as usize).clamp(1, MAX_TOON_BANDS);
+		let step = 1.0 / count as f32;
+
+		let mut bands = [(0.0, 0.0); MAX_TOON_BANDS];
+
+		for (i, band) in bands.iter_mut().take(count).enumerate() {
+			*band = (i as f32 * step, (i + 1) as f32 * step);
+		}
+
+		Self::new(&bands[..count])
+	}
+
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Diffuse factor of a surface at `ndotl`, the shade of the highest
+	/// band it is above.
+	#[inline]
+	pub fn shade(&self, ndotl: f32) -> f32 {
+		self.thresholds[..self.len]
+			.iter()
+			.rposition(|&threshold| ndotl > threshold)
+			.map_or(0.0, |band| self.shades[band])
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RimLight {
+	pub color: Color,
+	/// Part of the silhouette covered, from 0 to 1.
+	pub width: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct InkOutline {
+	pub color: Color,
+	/// Thickness in pixels.
+	pub width: f32,
+}
+
+/// Which faces are discarded before rasterization.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CullMode {
+	#[default]
+	Back,
+	Front,
+	/// Both sides are drawn, back faces are lit with a flipped normal.
+	None,
+}
+
+/// Winding of the front faces as seen on screen.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontFace {
+	#[default]
+	CounterClockwise,
+	Clockwise,
+}
+
+/// Depth comparison of an incoming fragment against the depth buffer. It
+/// is expressed for the standard depth range, `Less` means closer to the
+/// camera, and flipped by the renderer when reverse-Z is enabled.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthCompare {
+	Never,
+	#[default]
+	Less,
+	Equal,
+	LessEqual,
+	Greater,
+	GreaterEqual,
+	Always,
+}
+
+impl DepthCompare {
+	#[inline(always)]
+	pub fn passes(self, z: f32, depth: f32) -> bool {
+		match self {
+			DepthCompare::Never => false,
+			DepthCompare::Less => z < depth,
+			DepthCompare::Equal => z == depth,
+			DepthCompare::LessEqual => z <= depth,
+			DepthCompare::Greater => z > depth,
+			DepthCompare::GreaterEqual => z >= depth,
+			DepthCompare::Always => true,
+		}
+	}
+
+	/// Same comparison for a depth range running the other way.
+	#[inline(always)]
+	pub fn reversed(self) -> Self {
+		match self {
+			DepthCompare::Less => DepthCompare::Greater,
+			DepthCompare::LessEqual => DepthCompare::GreaterEqual,
+			DepthCompare::Greater => DepthCompare::Less,
+			DepthCompare::GreaterEqual => DepthCompare::LessEqual,
+			other => other,
+		}
+	}
+}
+
+/// Comparison of the masked stencil reference against the masked value in
+/// the stencil buffer, `Less` passes when the reference is lower.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilCompare {
+	Never,
+	Less,
+	Equal,
+	LessEqual,
+	Greater,
+	NotEqual,
+	GreaterEqual,
+	#[default]
+	Always,
+}
+
+/// Update applied to the stencil value, limited by the write mask.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilOp {
+	#[default]
+	Keep,
+	Zero,
+	Replace,
+	/// Increment clamped to the largest value below the reserved bits.
+	Increment,
+	/// Decrement clamped to 0.
+	Decrement,
+	IncrementWrap,
+	DecrementWrap,
+	Invert,
+}
+
+/// Stencil bits reserved by the renderer to mark the selected object.
+/// They are masked out of every state, which never reads nor writes them.
+pub const RESERVED_STENCIL_BITS: u8 = 0x80;
+
+/// Stencil test and the operations applied depending on its outcome. The
+/// masks never cover the [`RESERVED_STENCIL_BITS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilState {
+	pub compare: StencilCompare,
+	pub reference: u8,
+	pub read_mask: u8,
+	pub write_mask: u8,
+	/// Applied when the stencil test fails.
+	pub fail: StencilOp,
+	/// Applied when the stencil test passes but the depth test fails.
+	pub depth_fail: StencilOp,
+	/// Applied when both tests pass.
+	pub pass: StencilOp,
+}
+
+impl Default for StencilState {
+	fn default() -> Self {
+		Self {
+			compare: StencilCompare::Always,
+			reference: 0,
+			read_mask: 0xFF,
+			write_mask: 0xFF,
+			fail: StencilOp::Keep,
+			depth_fail: StencilOp::Keep,
+			pass: StencilOp::Keep,
+		}
+	}
+}
+
+impl StencilState {
+	#[inline(always)]
+	pub fn test(&self, value: u8) -> bool {
+		let read_mask = self.read_mask & !RESERVED_STENCIL_BITS;
+		let reference = self.reference & read_mask;
+		let value = value & read_mask;
+
+		match self.compare {
+			StencilCompare::Never => false,
+			StencilCompare::Less => reference < value,
+			StencilCompare::Equal => reference == value,
+			StencilCompare::LessEqual => reference <= value,
+			StencilCompare::Greater => reference > value,
+			StencilCompare::NotEqual => reference != value,
+			StencilCompare::GreaterEqual => reference >= value,
+			StencilCompare::Always => true,
+		}
+	}
+
+	/// New stencil value after applying `op` to `value`.
+	#[inline(always)]
+	pub fn apply(&self, op: StencilOp, value: u8) -> u8 {
+		// The arithmetic stays within the bits left to the materials.
+		let max = !RESERVED_STENCIL_BITS;
+		let current = value & max;
+
+		let updated = match op {
+			StencilOp::Keep => value,
+			StencilOp::Zero => 0,
+			StencilOp::Replace => self.reference,
+			StencilOp::Increment => current.saturating_add(1).min(max),
+			StencilOp::Decrement => current.saturating_sub(1),
+			StencilOp::IncrementWrap => current.wrapping_add(1) & max,
+			StencilOp::DecrementWrap => current.wrapping_sub(1) & max,
+			StencilOp::Invert => !value,
+		};
+
+		let write_mask = self.write_mask & max;
+
+		(value & !write_mask) | (updated & write_mask)
+	}
 }
 
 pub struct Material {
 	/// Shader selector per object.
 	pub shader: ShaderModel,
 
+	pub cull_mode: CullMode,
+
+	pub front_face: FrontFace,
+
+	pub depth_compare: DepthCompare,
+
+	/// Whether passing fragments update the depth buffer.
+	pub depth_write: bool,
+
+	/// Stencil test and operations, disabled when not set.
+	pub stencil: Option<StencilState>,
+
 	/// This controls the size of the specular highlight.
 	/// Its the exponent on specular factor.
 	pub shininess: f32,
@@ -33,6 +332,10 @@ pub struct Material {
 	/// wooden/plastic surface matte.
 	pub specular: Color,
 
+	/// Light emitted by the surface regardless of the lighting, black for
+	/// surfaces which do not glow.
+	pub emissive: Color,
+
 	/// A diffuse map, this gives the color texture to the object.
 	/// If not present, the shader will use the diffuse color.
 	pub albedo: Option<AlbedoHandle>,
@@ -40,19 +343,63 @@ pub struct Material {
 	/// A normal map, enables the shader to mimic the surface details.
 	/// If not  present the shader will use a flat normal.
 	pub normal: Option<NormalHandle>,
+
+	/// A height map, the shader offsets the texture coordinates along the
+	/// view direction to give the surface a parallax depth.
+	pub height: Option<HeightHandle>,
+
+	/// Depth of the relief in texture space, the lowest point of the height
+	/// map sits this far below the surface.
+	pub height_scale: f32,
+
+	/// An emission map, replaces the emissive color when present.
+	pub emissive_map: Option<EmissiveHandle>,
+
+	/// A specular mask, scales the specular color per texel so only the
+	/// glossy parts of the surface catch highlights.
+	pub specular_map: Option<SpecularHandle>,
+
+	/// A baked ambient occlusion map, darkens the ambient term of the
+	/// creases on top of the screen space occlusion.
+	pub occlusion_map: Option<OcclusionHandle>,
+
+	/// Bands, highlight, rim and outline of the toon shading.
+	pub toon: ToonSettings,
+
+	/// A ramp texture for the toon shading, the diffuse shade is read along
+	/// its u axis from the light facing angle, dark to lit.
+	pub toon_ramp: Option<AlbedoHandle>,
+
+	/// A matcap for the `ShaderModel::Matcap` shading, the surface facing
+	/// the camera reads its center.
+	pub matcap: Option<MatcapHandle>,
 }
 
 impl Default for Material {
 	fn default() -> Self {
 		Self {
-			shader: ShaderModel::Flat,
+			shader: ShaderModel::default(),
+			cull_mode: CullMode::default(),
+			front_face: FrontFace::default(),
+			depth_compare: DepthCompare::default(),
+			depth_write: true,
+			stencil: None,
 			shininess: 8.0,
 			specular_strength: 0.5,
 			diffuse: Color::from_hex_unchecked("#716f6f"),
 			ambient: Color::new_rgb_splat(0.5),
 			specular: Color::BLACK,
+			emissive: Color::BLACK,
 			albedo: None,
 			normal: None,
+			height: None,
+			height_scale: 0.05,
+			emissive_map: None,
+			specular_map: None,
+			occlusion_map: None,
+			toon: ToonSettings::default(),
+			toon_ramp: None,
+			matcap: None,
 		}
 	}
 }
@@ -67,6 +414,26 @@ impl Material {
 		self.shader = shader;
 	}
 
+	pub fn set_cull_mode(&mut self, cull_mode: CullMode) {
+		self.cull_mode = cull_mode;
+	}
+
+	pub fn set_front_face(&mut self, front_face: FrontFace) {
+		self.front_face = front_face;
+	}
+
+	pub fn set_depth_compare(&mut self, depth_compare: DepthCompare) {
+		self.depth_compare = depth_compare;
+	}
+
+	pub fn set_depth_write(&mut self, enabled: bool) {
+		self.depth_write = enabled;
+	}
+
+	pub fn set_stencil(&mut self, stencil: StencilState) {
+		self.stencil = Some(stencil);
+	}
+
 	#[inline]
 	pub fn set_shininess(&mut self, shininess: f32) {
 		self.shininess = shininess.clamp(Self::MIN_SHINE, Self::MAX_SHINE);
@@ -86,24 +453,251 @@ impl Material {
 		self.normal = Some(handle)
 	}
 
+	pub fn set_height_map(&mut self, handle: HeightHandle) {
+		self.height = Some(handle)
+	}
+
+	#[inline]
+	pub fn set_height_scale(&mut self, scale: f32) {
+		self.height_scale = scale.max(0.0);
+	}
+
+	pub fn set_emissive(&mut self, emissive: Color) {
+		self.emissive = emissive;
+	}
+
+	pub fn set_emissive_map(&mut self, handle: EmissiveHandle) {
+		self.emissive_map = Some(handle)
+	}
+
+	pub fn set_specular_map(&mut self, handle: SpecularHandle) {
+		self.specular_map = Some(handle)
+	}
+
+	pub fn set_occlusion_map(&mut self, handle: OcclusionHandle) {
+		self.occlusion_map = Some(handle)
+	}
+
+	pub fn set_toon(&mut self, settings: ToonSettings) {
+		self.toon = settings;
+	}
+
+	pub fn set_toon_ramp(&mut self, handle: AlbedoHandle) {
+		self.toon_ramp = Some(handle)
+	}
+
+	pub fn set_matcap(&mut self, handle: MatcapHandle) {
+		self.matcap = Some(handle)
+	}
+
 	pub fn resolve<'m>(&'m self, registry: &'m AssetRegistry) -> MaterialRef<'m> {
 		MaterialRef {
+			shader: self.shader,
+			cull_mode: self.cull_mode,
+			front_face: self.front_face,
+			depth_compare: self.depth_compare,
+			depth_write: self.depth_write,
+			stencil: self.stencil,
 			shininess: self.shininess,
 			diffuse: self.diffuse,
 			ambient: self.ambient,
 			specular: self.specular,
+			emissive: self.emissive,
 			albedo: self.albedo.as_ref().and_then(|h| registry.get_albedo(h)),
 			normal: self.normal.as_ref().and_then(|h| registry.get_normal(h)),
+			height: self.height.as_ref().and_then(|h| registry.get_height(h)),
+			height_scale: self.height_scale,
+			emissive_map: self
+				.emissive_map
+				.as_ref()
+				.and_then(|h| registry.get_emissive(h)),
+			specular_map: self
+				.specular_map
+				.as_ref()
+				.and_then(|h| registry.get_specular(h)),
+			occlusion_map: self
+				.occlusion_map
+				.as_ref()
+				.and_then(|h| registry.get_occlusion(h)),
+			toon: self.toon,
+			toon_ramp: self.toon_ramp.as_ref().and_then(|h| registry.get_albedo(h)),
+			matcap: self.matcap.as_ref().and_then(|h| registry.get_matcap(h)),
 		}
 	}
 }
 
 #[derive(Clone, Copy)]
 pub struct MaterialRef<'m> {
+	pub shader: ShaderModel,
+	pub cull_mode: CullMode,
+	pub front_face: FrontFace,
+	pub depth_compare: DepthCompare,
+	pub depth_write: bool,
+	pub stencil: Option<StencilState>,
 	pub shininess: f32,
 	pub diffuse: Color,
 	pub ambient: Color,
 	pub specular: Color,
+	pub emissive: Color,
 	pub albedo: Option<&'m Albedo>,
 	pub normal: Option<&'m NormalMap>,
+	pub height: Option<&'m HeightMap>,
+	pub height_scale: f32,
+	pub emissive_map: Option<&'m EmissiveMap>,
+	pub specular_map: Option<&'m SpecularMap>,
+	pub occlusion_map: Option<&'m OcclusionMap>,
+	pub toon: ToonSettings,
+	pub toon_ramp: Option<&'m Albedo>,
+	pub matcap: Option<&'m MatcapMap>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		DepthCompare, MAX_TOON_BANDS, RESERVED_STENCIL_BITS, StencilCompare,
+		StencilOp, StencilState, ToonBands,
+	};
+
+	#[test]
+	fn even_bands_snap_up() {
+		let bands = ToonBands::even(3);
+
+		// Same as ceil(ndotl * 3) / 3 on the lit side.
+		for i in 0..=30 {
+			let ndotl = i as f32 / 30.0;
+			let expected = (ndotl * 3.0).ceil() / 3.0;
+
+			assert!((bands.shade(ndotl) - expected).abs() < 1e-6, "{ndotl}");
+		}
+
+		assert_eq!(bands.shade(-0.5), 0.0);
+	}
+
+	#[test]
+	fn custom_thresholds() {
+		// Given out of order, with an unlit band on the shadow side.
+		let bands = ToonBands::new(&[(0.8, 1.0), (-1.0, 0.1), (0.2, 0.6)]);
+
+		assert_eq!(bands.len(), 3);
+		assert_eq!(bands.shade(-0.5), 0.1);
+		assert_eq!(bands.shade(0.5), 0.6);
+		assert_eq!(bands.shade(0.9), 1.0);
+		assert_eq!(bands.shade(-1.0), 0.0);
+	}
+
+	#[test]
+	fn bands_are_bounded() {
+		let bands = [(0.0, 1.0); MAX_TOON_BANDS + 4];
+
+		assert_eq!(ToonBands::new(&bands).len(), MAX_TOON_BANDS);
+		assert_eq!(ToonBands::even(100).len(), MAX_TOON_BANDS);
+		assert_eq!(ToonBands::even(0).len(), 1);
+	}
+
+	#[test]
+	fn stencil_ignores_the_reserved_bits() {
+		let state = StencilState {
+			compare: StencilCompare::Equal,
+			reference: 0xFF,
+			..Default::default()
+		};
+
+		assert!(state.test(0x7F));
+		assert!(!state.test(0x7E));
+
+		let selected = RESERVED_STENCIL_BITS | 0x01;
+
+		assert_eq!(
+			state.apply(StencilOp::Zero, selected),
+			RESERVED_STENCIL_BITS
+		);
+		assert_eq!(state.apply(StencilOp::Replace, 0), 0x7F);
+		assert_eq!(state.apply(StencilOp::Invert, selected), 0xFE);
+	}
+
+	#[test]
+	fn stencil_ops_update_the_value() {
+		let state = StencilState {
+			reference: 0x05,
+			..Default::default()
+		};
+
+		assert_eq!(state.apply(StencilOp::Keep, 0x12), 0x12);
+		assert_eq!(state.apply(StencilOp::Zero, 0x12), 0x00);
+		assert_eq!(state.apply(StencilOp::Replace, 0x12), 0x05);
+		assert_eq!(state.apply(StencilOp::Increment, 0x12), 0x13);
+		assert_eq!(state.apply(StencilOp::Decrement, 0x12), 0x11);
+		assert_eq!(state.apply(StencilOp::Invert, 0x12), 0x6D);
+
+		// Clamped, or wrapped within the bits left to the materials.
+		assert_eq!(state.apply(StencilOp::Increment, 0x7F), 0x7F);
+		assert_eq!(state.apply(StencilOp::Decrement, 0x00), 0x00);
+		assert_eq!(state.apply(StencilOp::IncrementWrap, 0x7F), 0x00);
+		assert_eq!(state.apply(StencilOp::DecrementWrap, 0x00), 0x7F);
+	}
+
+	#[test]
+	fn stencil_write_mask_limits_the_update() {
+		let state = StencilState {
+			reference: 0xFF,
+			write_mask: 0x0F,
+			..Default::default()
+		};
+
+		assert_eq!(state.apply(StencilOp::Replace, 0x30), 0x3F);
+		assert_eq!(state.apply(StencilOp::Zero, 0x3C), 0x30);
+	}
+
+	#[test]
+	fn stencil_compare_uses_the_read_mask() {
+		let compare = |compare, value| {
+			let state = StencilState {
+				compare,
+				reference: 0x12,
+				read_mask: 0x0F,
+				..Default::default()
+			};
+
+			state.test(value)
+		};
+
+		// Only the low nibble is compared, 2 against 3.
+		assert!(compare(StencilCompare::Less, 0x43));
+		assert!(compare(StencilCompare::LessEqual, 0x43));
+		assert!(compare(StencilCompare::NotEqual, 0x43));
+		assert!(!compare(StencilCompare::Greater, 0x43));
+		assert!(!compare(StencilCompare::Equal, 0x43));
+		assert!(compare(StencilCompare::Equal, 0x72));
+		assert!(compare(StencilCompare::GreaterEqual, 0x72));
+		assert!(compare(StencilCompare::Always, 0x00));
+		assert!(!compare(StencilCompare::Never, 0x12));
+	}
+
+	#[test]
+	fn reversed_depth_compare_matches_the_flipped_range() {
+		let compares = [
+			DepthCompare::Never,
+			DepthCompare::Less,
+			DepthCompare::Equal,
+			DepthCompare::LessEqual,
+			DepthCompare::Greater,
+			DepthCompare::GreaterEqual,
+			DepthCompare::Always,
+		];
+		let depths = [0.0, 0.25, 0.5, 1.0];
+
+		// Reverse-Z stores 1 - z, the reversed compare must agree on every
+		// pair of depths.
+		for compare in compares {
+			for z in depths {
+				for depth in depths {
+					assert_eq!(
+						compare.passes(z, depth),
+						compare.reversed().passes(1.0 - z, 1.0 - depth),
+						"{compare:?} {z} {depth}"
+					);
+				}
+			}
+		}
+	}
 }
@@ -6,6 +6,7 @@ use {
 		texture::{AlbedoMap as Albedo, NormalMap},
 	},
 	pcore::{
+		color::Color,
 		geometry::Mesh,
 		math::{Matrix4, Vector3},
 	},
@@ -44,19 +45,7 @@ impl Object {
 	}
 
 	pub fn resolve<'m>(&'m self, registry: &'m AssetRegistry) -> ObjectRef<'m> {
-		let m_model = Matrix4::from_transforms(
-			self.transform.position,
-			self.transform.scale,
-			self.transform.rotation,
-		);
-
-		let m_normal = m_model.inverse().transpose();
-
-		ObjectRef {
-			model: self.model.resolve(registry),
-			m_model,
-			m_normal,
-		}
+		ObjectRef::new(self.model.resolve(registry), &self.transform, Color::WHITE)
 	}
 
 	pub fn from_mesh_texture(mesh: Mesh, texture: Albedo) -> Self {
@@ -88,6 +77,7 @@ impl Object {
 	}
 }
 
+#[derive(Clone, Copy)]
 pub struct Transform {
 	pub scale: Vector3,
 	pub position: Vector3,
@@ -104,9 +94,109 @@ impl Default for Transform {
 	}
 }
 
+impl Transform {
+	pub fn matrix(&self) -> Matrix4 {
+		Matrix4::from_transforms(self.position, self.scale, self.rotation)
+	}
+}
+
 #[derive(Clone, Copy)]
 pub struct ObjectRef<'m> {
 	pub model: ModelRef<'m>,
 	pub m_model: Matrix4,
 	pub m_normal: Matrix4,
+	/// Multiplied with the base color, white for regular objects.
+	pub tint: Color,
+}
+
+impl<'m> ObjectRef<'m> {
+	pub fn new(model: ModelRef<'m>, transform: &Transform, tint: Color) -> Self {
+		Self::from_instance(model, InstanceRef::new(transform, tint))
+	}
+
+	pub fn from_instance(model: ModelRef<'m>, instance: InstanceRef) -> Self {
+		Self {
+			model,
+			m_model: instance.m_model,
+			m_normal: instance.m_normal,
+			tint: instance.tint,
+		}
+	}
+}
+
+/// Matrices and tint of a copy of an instanced model.
+#[derive(Clone, Copy)]
+pub struct InstanceRef {
+	pub m_model: Matrix4,
+	pub m_normal: Matrix4,
+	pub tint: Color,
+}
+
+impl InstanceRef {
+	pub fn new(transform: &Transform, tint: Color) -> Self {
+		let m_model = transform.matrix();
+		let m_normal = m_model.inverse().transpose();
+
+		Self {
+			m_model,
+			m_normal,
+			tint,
+		}
+	}
+}
+
+/// A single copy of an instanced model.
+#[derive(Clone, Copy, Default)]
+pub struct Instance {
+	pub transform: Transform,
+	/// Multiplied with the base color of the model.
+	pub tint: Option<Color>,
+}
+
+impl Instance {
+	pub fn new(transform: Transform) -> Self {
+		Self {
+			transform,
+			tint: None,
+		}
+	}
+
+	pub fn with_tint(mut self, tint: Color) -> Self {
+		self.tint = Some(tint);
+		self
+	}
+}
+
+/// Many copies of the same model, the model is resolved once for the whole
+/// group and only the transforms differ between the copies.
+#[derive(Default)]
+pub struct InstanceGroup {
+	pub model: Model,
+	pub instances: Vec<Instance>,
+}
+
+impl InstanceGroup {
+	pub fn new(model: Model) -> Self {
+		Self {
+			model,
+			instances: Vec::new(),
+		}
+	}
+
+	pub fn push(&mut self, instance: Instance) {
+		self.instances.push(instance);
+	}
+
+	/// The model, resolved once, and the matrices of every copy.
+	pub fn resolve<'m>(
+		&'m self,
+		registry: &'m AssetRegistry,
+	) -> (ModelRef<'m>, impl Iterator<Item = InstanceRef> + 'm) {
+		let instances = self.instances.iter().map(|instance| {
+			let tint = instance.tint.unwrap_or(Color::WHITE);
+			InstanceRef::new(&instance.transform, tint)
+		});
+
+		(self.model.resolve(registry), instances)
+	}
 }
@@ -8,27 +8,38 @@ use {
 pub type AlbedoMap = TextureMap<Color>;
 pub type TAlbedo = Texture<Color, AlbedoMap>;
 
+/// Light emitted by the surface, added on top of the lit color.
+pub type EmissiveMap = AlbedoMap;
+
+/// Lit sphere looked up by the view space normal, it holds the whole
+/// shading of the `ShaderModel::Matcap` surfaces.
+pub type MatcapMap = AlbedoMap;
+
 impl AlbedoMap {
 	pub fn load<P>(path: P, wrap: Wrap) -> PResult<Self>
 	where
 		P: AsRef<Path>,
 	{
-		let converter = |p: Rgb<u8>| -> Color {
-			Color::new_rgb(
-				p[0] as f32 / 255.0,
-				p[1] as f32 / 255.0,
-				p[2] as f32 / 255.0,
-			)
-		};
+		// Color maps are authored in sRGB, shading works on linear values.
+		let converter =
+			|p: Rgb<u8>| -> Color { Color::from_srgba8([p[0], p[1], p[2], 255]) };
 
-		let averager = |c0: Color, c1: Color, c2: Color, c3: Color| -> Color {
-			(c0 + c1 + c2 + c3) * 0.25
-		};
+		TextureMap::<Color>::from_file(path, wrap, converter, average)
+	}
 
-		TextureMap::<Color>::from_file(path, wrap, converter, averager)
+	/// Replaces the texels with `data` of the given size and rebuilds the
+	/// mip chain, e.g. after rendering into the texture.
+	pub fn update(&mut self, width: usize, height: usize, data: Vec<Color>) {
+		*self = Self::new(width, height, data, self.wrap);
+		self.bake(average);
 	}
 }
 
+#[inline]
+fn average(c0: Color, c1: Color, c2: Color, c3: Color) -> Color {
+	(c0 + c1 + c2 + c3) * 0.25
+}
+
 impl TextureSampler for AlbedoMap {
 	type Out = Color;
 
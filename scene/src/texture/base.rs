@@ -202,11 +202,16 @@ impl<T> TextureMap<T> {
 		Ok(tex)
 	}
 
+	/// Builds the mip chain from the base level, levels from a previous
+	/// bake are regenerated. Texels are averaged as stored, so color maps
+	/// must hold linear values for the filtering to be correct.
 	pub fn bake<A>(&mut self, averager: A)
 	where
 		T: Copy + Arithmetic + Default,
 		A: Fn(T, T, T, T) -> T,
 	{
+		self.mipmap.truncate(1);
+
 		let mut level = 0;
 		loop {
 			let base = &self.mipmap[level];
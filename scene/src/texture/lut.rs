@@ -0,0 +1,273 @@
+use {
+	pcore::{
+		error::{FileError, PResult},
+		math::Vector3,
+	},
+	std::{fs, path::Path},
+};
+
+/// Largest lattice accepted from a file, the `.cube` specification stops
+/// at 256 too.
+pub const MAX_LUT_SIZE: usize = 256;
+
+/// Color grading look up table, a lattice of output colors over the RGB
+/// cube of input colors. Colors in between are trilinearly interpolated.
+#[derive(Debug, Clone)]
+pub struct ColorLut {
+	size: usize,
+	domain_min: Vector3,
+	domain_max: Vector3,
+	/// Output colors, red varies first then green then blue.
+	data: Vec<Vector3>,
+}
+
+impl ColorLut {
+	/// Builds a table of `size` entries per channel from its output colors,
+	/// ordered red first.
+	pub fn new(size: usize, data: Vec<Vector3>) -> PResult<Self> {
+		if !(2..=MAX_LUT_SIZE).contains(&size) {
+			return Err(wrong_file(format!("unsupported LUT size {size}")));
+		}
+
+		if data.len() != size * size * size {
+			return Err(wrong_file(format!(
+				"expected {} LUT entries, found {}",
+				size * size * size,
+				data.len()
+			)));
+		}
+
+		Ok(Self {
+			size,
+			domain_min: Vector3::new(0.0, 0.0, 0.0),
+			domain_max: Vector3::new(1.0, 1.0, 1.0),
+			data,
+		})
+	}
+
+	/// Table leaving every color unchanged.
+	pub fn identity(size: usize) -> PResult<Self> {
+		let scale = 1.0 / (size.max(2) - 1) as f32;
+
+		let data = (0..size * size * size)
+			.map(|i| {
+				let (r, g, b) = (i % size, i / size % size, i / (size * size));
+				Vector3::new(r as f32, g as f32, b as f32) * scale
+			})
+			.collect();
+
+		Self::new(size, data)
+	}
+
+	/// Loads an Adobe / Resolve `.cube` 3D table.
+	pub fn load_cube<P>(path: P) -> PResult<Self>
+	where
+		P: AsRef<Path>,
+	{
+		Self::parse_cube(&fs::read_to_string(path)?)
+	}
+
+	/// Parses the text of a `.cube` 3D table. 1D tables are rejected and
+	/// the keywords not affecting the table are skipped.
+	pub fn parse_cube(source: &str) -> PResult<Self> {
+		let mut size = None;
+		let mut domain_min = Vector3::new(0.0, 0.0, 0.0);
+		let mut domain_max = Vector3::new(1.0, 1.0, 1.0);
+		let mut data = vec![];
+
+		for line in source.lines() {
+			let statement = line.split('#').next().unwrap_or_default();
+			let parts = statement.split_whitespace().collect::<Vec<_>>();
+
+			if parts.is_empty() {
+				continue;
+			}
+
+			// Data lines are the only ones starting with a number.
+			if parts[0].starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c))
+			{
+				data.push(parse_triplet(&parts)?);
+				continue;
+			}
+
+			match parts[0] {
+				"LUT_3D_SIZE" => {
+					let value = parts.get(1).ok_or(FileError::Invalid)?;
+					size = Some(value.parse::<usize>()?);
+				}
+				"LUT_1D_SIZE" => {
+					return Err(wrong_file("1D LUTs are not supported".into()));
+				}
+				"DOMAIN_MIN" => domain_min = parse_triplet(&parts[1..])?,
+				"DOMAIN_MAX" => domain_max = parse_triplet(&parts[1..])?,
+				// Resolve flavour of the domain, shared by the channels.
+				"LUT_3D_INPUT_RANGE" => {
+					let [min, max] = match &parts[1..] {
+						[min, max] => [min.parse::<f32>()?, max.parse::<f32>()?],
+						_ => return Err(FileError::Invalid.into()),
+					};
+
+					domain_min = Vector3::new(min, min, min);
+					domain_max = Vector3::new(max, max, max);
+				}
+				_ => continue,
+			}
+		}
+
+		let size = size.ok_or_else(|| wrong_file("missing LUT_3D_SIZE".into()))?;
+
+		let mut lut = Self::new(size, data)?;
+		lut.domain_min = domain_min;
+		lut.domain_max = domain_max;
+
+		Ok(lut)
+	}
+
+	/// Loads a table unwrapped into an image strip, a row of `size` square
+	/// slices of increasing blue with red growing to the right and green
+	/// downwards. The texels are kept as encoded, like the colors graded.
+	pub fn load_strip<P>(path: P) -> PResult<Self>
+	where
+		P: AsRef<Path>,
+	{
+		let img = image::open(path)?.to_rgb8();
+		let (w, h) = img.dimensions();
+		let size = h as usize;
+
+		if w as usize != size * size {
+			return Err(wrong_file(format!(
+				"a LUT strip of height {h} must be {} wide, found {w}",
+				size * size
+			)));
+		}
+
+		let data = (0..size * size * size)
+			.map(|i| {
+				let (r, g, b) = (i % size, i / size % size, i / (size * size));
+				let p = img.get_pixel((b * size + r) as u32, g as u32);
+
+				Vector3::new(p[0] as f32, p[1] as f32, p[2] as f32) * (1.0 / 255.0)
+			})
+			.collect();
+
+		Self::new(size, data)
+	}
+
+	pub fn size(&self) -> usize {
+		self.size
+	}
+
+	/// Graded `color`, interpolated between the 8 closest entries. Colors
+	/// outside the domain are clamped to it.
+	pub fn sample(&self, color: Vector3) -> Vector3 {
+		let max = (self.size - 1) as f32;
+
+		let cell = |c: f32, min: f32, max_c: f32| {
+			let t =
+				((c - min) / (max_c - min).max(f32::EPSILON)).clamp(0.0, 1.0) * max;
+			let i = (t as usize).min(self.size - 2);
+
+			(i, t - i as f32)
+		};
+
+		let (r, tr) = cell(color.x, self.domain_min.x, self.domain_max.x);
+		let (g, tg) = cell(color.y, self.domain_min.y, self.domain_max.y);
+		let (b, tb) = cell(color.z, self.domain_min.z, self.domain_max.z);
+
+		let at = |r: usize, g: usize, b: usize| {
+			self.data[(b * self.size + g) * self.size + r]
+		};
+		let lerp = |a: Vector3, b: Vector3, t: f32| a + (b - a) * t;
+
+		let slice = |b: usize| {
+			let bottom = lerp(at(r, g, b), at(r + 1, g, b), tr);
+			let top = lerp(at(r, g + 1, b), at(r + 1, g + 1, b), tr);
+
+			lerp(bottom, top, tg)
+		};
+
+		lerp(slice(b), slice(b + 1), tb)
+	}
+}
+
+fn parse_triplet(parts: &[&str]) -> PResult<Vector3> {
+	match parts {
+		[r, g, b] => Ok(Vector3::new(r.parse()?, g.parse()?, b.parse()?)),
+		_ => Err(wrong_file(format!("expected 3 values, found {parts:?}"))),
+	}
+}
+
+#[inline]
+fn wrong_file(message: String) -> pcore::error::PError {
+	FileError::WrongFile(message).into()
+}
+
+#[cfg(test)]
+mod tests {
+	use {super::ColorLut, pcore::math::Vector3};
+
+	#[test]
+	fn parse_cube_interpolates() {
+		// Inverts the red channel.
+		let source = r#"
+			TITLE "invert red"
+			# comment
+			LUT_3D_SIZE 2
+			1 0 0
+			0 0 0
+			1 1 0
+			0 1 0
+			1 0 1
+			0 0 1
+			1 1 1
+			0 1 1
+		"#;
+
+		let lut = ColorLut::parse_cube(source).unwrap();
+		let out = lut.sample(Vector3::new(0.25, 0.5, 0.75));
+
+		assert!((out.x - 0.75).abs() < 1e-5);
+		assert!((out.y - 0.5).abs() < 1e-5);
+		assert!((out.z - 0.75).abs() < 1e-5);
+	}
+
+	#[test]
+	fn parse_cube_input_range() {
+		// Identity over [0, 2], with a keyword of another tool on the way.
+		let source = r#"
+			TITLE "half"
+			LUT_3D_SIZE 2
+			LUT_3D_INPUT_RANGE 0.0 2.0
+			LUT_1D_INPUT_RANGE 0.0 1.0
+			0 0 0
+			2 0 0
+			0 2 0
+			2 2 0
+			0 0 2
+			2 0 2
+			0 2 2
+			2 2 2
+		"#;
+
+		let lut = ColorLut::parse_cube(source).unwrap();
+		let color = Vector3::new(0.5, 1.0, 1.5);
+
+		assert!((lut.sample(color) - color).magnitude() < 1e-5);
+	}
+
+	#[test]
+	fn parse_cube_rejects_bad_tables() {
+		assert!(ColorLut::parse_cube("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+		assert!(ColorLut::parse_cube("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+		assert!(ColorLut::parse_cube("LUT_3D_SIZE 2\n0 0 x\n").is_err());
+	}
+
+	#[test]
+	fn identity_keeps_colors() {
+		let lut = ColorLut::identity(17).unwrap();
+		let color = Vector3::new(0.1, 0.6, 0.93);
+		let out = lut.sample(color);
+
+		assert!((out - color).magnitude() < 1e-5);
+	}
+}
@@ -1,9 +1,11 @@
 use pcore::{geometry::UV, math::Arithmetic};
-pub use {albedo::*, base::*, bump::*};
+pub use {albedo::*, base::*, bump::*, lut::*, scalar::*};
 
 mod albedo;
 pub mod base;
 mod bump;
+mod lut;
+mod scalar;
 
 pub trait TextureSampler {
 	type Out: Copy + Arithmetic;
@@ -0,0 +1,85 @@
+use {
+	crate::texture::{Texture, TextureMap, TextureSampler, Wrap},
+	image::Rgb,
+	pcore::{error::PResult, math},
+	std::path::Path,
+};
+
+/// Single channel texture in the `[0, 1]` range.
+pub type ScalarMap = TextureMap<f32>;
+pub type TScalar = Texture<f32, ScalarMap>;
+
+/// Displacement of the surface, 1 being the top of the relief and 0 its
+/// deepest point.
+pub type HeightMap = ScalarMap;
+pub type THeight = TScalar;
+
+/// Strength of the specular highlight, 0 turns it off.
+pub type SpecularMap = ScalarMap;
+
+/// Baked ambient occlusion, 1 being unoccluded.
+pub type OcclusionMap = ScalarMap;
+
+impl ScalarMap {
+	pub fn load<P>(path: P, wrap: Wrap) -> PResult<Self>
+	where
+		P: AsRef<Path>,
+	{
+		// Grayscale maps store the same value in every channel, colored
+		// ones are reduced to their luminance.
+		let converter = |p: Rgb<u8>| -> f32 {
+			(0.2126 * p[0] as f32 + 0.7152 * p[1] as f32 + 0.0722 * p[2] as f32)
+				/ 255.0
+		};
+
+		let averager = |h0: f32, h1: f32, h2: f32, h3: f32| -> f32 {
+			(h0 + h1 + h2 + h3) * 0.25
+		};
+
+		TextureMap::<f32>::from_file(path, wrap, converter, averager)
+	}
+}
+
+impl TextureSampler for ScalarMap {
+	type Out = f32;
+
+	#[inline(always)]
+	fn sample(&self, u: f32, v: f32, lod: f32) -> Self::Out {
+		let lod = self.clamp_lod(lod);
+		let mip = self.unsafe_get_level(lod as usize);
+
+		let u = (u * mip.width as f32 - 0.5) as usize;
+		let v = (v * mip.height as f32 - 0.5) as usize;
+
+		mip.unsafe_texel(u, v)
+	}
+
+	#[inline(always)]
+	fn bi_sample(&self, u: f32, v: f32, lod: f32) -> Self::Out {
+		let u = self.wrap_uv(u);
+		let mut v = self.wrap_uv(v);
+
+		v = 1.0 - v;
+
+		let lod = self.clamp_lod(lod);
+		let mip = self.unsafe_get_level(lod as usize);
+
+		let x = u * (mip.width as f32 - 1.0);
+		let y = v * (mip.height as f32 - 1.0);
+
+		let x0 = x.floor() as usize;
+		let y0 = y.floor() as usize;
+		let x1 = (x0 + 1).min(mip.width - 1);
+		let y1 = (y0 + 1).min(mip.height - 1);
+
+		let tx = x - x0 as f32;
+		let ty = y - y0 as f32;
+
+		let h00 = mip.unsafe_texel(x0, y0);
+		let h10 = mip.unsafe_texel(x1, y0);
+		let h01 = mip.unsafe_texel(x0, y1);
+		let h11 = mip.unsafe_texel(x1, y1);
+
+		math::bi_lerp(h00, h01, h10, h11, tx, ty)
+	}
+}